@@ -0,0 +1,73 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+#[path = "src/civil_date.rs"]
+mod civil_date;
+
+const WORKSPACE_MANIFEST: &str = "workspace/repos.toml";
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed={WORKSPACE_MANIFEST}");
+    // Anything that can make the tree dirty, so ORCHESTRATOR_GIT_DIRTY stays accurate.
+    for path in ["src", "release", "workspace", "Cargo.toml", "Cargo.lock"] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let sha = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ORCHESTRATOR_GIT_SHA={sha}");
+
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some();
+    println!("cargo:rustc-env=ORCHESTRATOR_GIT_DIRTY={dirty}");
+
+    // Honor SOURCE_DATE_EPOCH so reproducible builds get a stable date.
+    let epoch = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!(
+        "cargo:rustc-env=ORCHESTRATOR_BUILD_DATE={}",
+        format_utc(epoch)
+    );
+
+    let manifest_hash = std::fs::read(WORKSPACE_MANIFEST)
+        .map(|bytes| {
+            Sha256::digest(&bytes)
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>()
+        })
+        .unwrap_or_else(|_| "unknown".to_string());
+    println!("cargo:rustc-env=ORCHESTRATOR_MANIFEST_SHA256={manifest_hash}");
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Formats a Unix timestamp as an RFC 3339 UTC date-time.
+fn format_utc(epoch: u64) -> String {
+    let (year, month, day) = civil_date::civil_date(epoch);
+    let secs = epoch % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3_600,
+        (secs % 3_600) / 60,
+        secs % 60
+    )
+}
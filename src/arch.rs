@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A target architecture the release chain builds assets for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub enum Arch {
+    #[value(name = "x86_64")]
+    X86_64,
+    #[value(name = "aarch64")]
+    Aarch64,
+}
+
+impl Arch {
+    /// Name used in release triples and asset file names.
+    pub fn name(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::Aarch64 => "aarch64",
+        }
+    }
+
+    /// Removable-media boot loader path name from the UEFI spec (`EFI/BOOT/<name>`).
+    pub fn efi_boot_name(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "BOOTX64.EFI",
+            Arch::Aarch64 => "BOOTAA64.EFI",
+        }
+    }
+
+    /// PE/COFF machine type of EFI images built for this arch.
+    pub fn pe_machine(self) -> u16 {
+        match self {
+            Arch::X86_64 => 0x8664,
+            Arch::Aarch64 => 0xaa64,
+        }
+    }
+
+    /// The arch a release asset is built for, judged by its file name.
+    ///
+    /// ISOs without an arch suffix are the x86_64 ones, which predate multi-arch releases.
+    pub fn of_asset(name: &str) -> Option<Arch> {
+        let arch = [Arch::X86_64, Arch::Aarch64]
+            .into_iter()
+            .find(|arch| name.contains(arch.name()) || name.contains(arch.efi_boot_name()));
+        match arch {
+            None if name.ends_with(".iso") || name.ends_with(".iso.sha256") => Some(Arch::X86_64),
+            arch => arch,
+        }
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Groups `missing` asset names by arch, e.g. `aarch64: a, b; x86_64: c`, so a waiting
+/// release shows which arch is holding it up.
+pub fn describe_missing(missing: &[String]) -> String {
+    let mut by_arch: BTreeMap<Option<Arch>, Vec<&str>> = BTreeMap::new();
+    for name in missing {
+        by_arch
+            .entry(Arch::of_asset(name))
+            .or_default()
+            .push(name.as_str());
+    }
+    by_arch
+        .into_iter()
+        .map(|(arch, names)| match arch {
+            Some(arch) => format!("{arch}: {}", names.join(", ")),
+            None => names.join(", "),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arch_of_asset() {
+        for (name, arch) in [
+            ("BOOTX64.EFI", Some(Arch::X86_64)),
+            ("BOOTAA64.EFI.sha256", Some(Arch::Aarch64)),
+            ("truthdb-v1.2.3-x86_64-linux-gnu.tar.gz", Some(Arch::X86_64)),
+            (
+                "truthdb-installer-v1.2.3-aarch64-linux-musl.sha256",
+                Some(Arch::Aarch64),
+            ),
+            ("truthdb-installer-v1.2.3.iso", Some(Arch::X86_64)),
+            ("truthdb-installer-v1.2.3.iso.sha256", Some(Arch::X86_64)),
+            ("truthdb-installer-v1.2.3-aarch64.iso", Some(Arch::Aarch64)),
+            ("manifest.json", None),
+        ] {
+            assert_eq!(Arch::of_asset(name), arch, "{name}");
+        }
+    }
+
+    #[test]
+    fn describe_missing_groups_by_arch() {
+        let missing: Vec<String> = [
+            "BOOTAA64.EFI",
+            "notes.txt",
+            "truthdb-v1-x86_64-linux-gnu.tar.gz",
+            "BOOTAA64.EFI.sha256",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            describe_missing(&missing),
+            "notes.txt; x86_64: truthdb-v1-x86_64-linux-gnu.tar.gz; aarch64: BOOTAA64.EFI, BOOTAA64.EFI.sha256"
+        );
+    }
+}
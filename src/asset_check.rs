@@ -0,0 +1,602 @@
+use crate::arch::Arch;
+use crate::release_definition::{AssetCheck, AssetCheckKind};
+use anyhow::{Context, Result, bail};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const ISO_SECTOR: u64 = 2048;
+/// El Torito platform ID for UEFI boot entries.
+const EL_TORITO_EFI: u8 = 0xef;
+const EL_TORITO_BOOTABLE: u8 = 0x88;
+/// Shortest directory record: the fixed 33-byte header plus a one-byte name.
+const ISO_MIN_RECORD_LEN: usize = 34;
+/// Largest directory extent we'll read; real installer ISOs stay far below this.
+const ISO_MAX_DIRECTORY_SIZE: u32 = 16 * 1024 * 1024;
+
+/// Runs `check` on the file at `path` and returns a one-line summary of what was found.
+///
+/// EFI images and ISOs whose file name names an arch must also be built for that arch.
+pub fn run_check(path: &Path, check: &AssetCheck) -> Result<String> {
+    let arch = path
+        .file_name()
+        .and_then(|name| Arch::of_asset(&name.to_string_lossy()));
+    match check.kind {
+        AssetCheckKind::TarGz => check_tar_gz(path, &check.contains),
+        AssetCheckKind::Pe => check_pe(path, arch),
+        AssetCheckKind::Iso => {
+            let mut contains = check.contains.clone();
+            if let Some(arch) = arch {
+                let loader = format!("EFI/BOOT/{}", arch.efi_boot_name());
+                if !contains.contains(&loader) {
+                    contains.push(loader);
+                }
+            }
+            check_iso(path, &contains)
+        }
+    }
+}
+
+fn check_tar_gz(path: &Path, contains: &[String]) -> Result<String> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    inspect_tar_gz(file, contains)
+}
+
+/// Reads every entry to the end, so a truncated or corrupt archive fails here.
+fn inspect_tar_gz(reader: impl Read, contains: &[String]) -> Result<String> {
+    let mut tar = tar::Archive::new(GzDecoder::new(reader));
+
+    let mut names = Vec::new();
+    for entry in tar.entries().context("failed to read archive")? {
+        let mut entry = entry.context("failed to read archive entry")?;
+        let name = entry
+            .path()
+            .context("invalid archive entry path")?
+            .to_string_lossy()
+            .into_owned();
+        std::io::copy(&mut entry, &mut std::io::sink())
+            .with_context(|| format!("archive entry {name} is truncated or corrupt"))?;
+        names.push(name);
+    }
+    if names.is_empty() {
+        bail!("archive is empty");
+    }
+
+    let missing: Vec<&str> = contains
+        .iter()
+        .filter(|wanted| !names.iter().any(|name| entry_matches(name, wanted)))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        bail!("archive is missing {}", missing.join(", "));
+    }
+    Ok(format!("{} entries, read cleanly", names.len()))
+}
+
+/// Matches an archive entry by full path (ignoring a leading `./`) or by file name.
+fn entry_matches(name: &str, wanted: &str) -> bool {
+    let name = name.trim_start_matches("./").trim_end_matches('/');
+    name == wanted || name.rsplit('/').next() == Some(wanted)
+}
+
+fn check_pe(path: &Path, arch: Option<Arch>) -> Result<String> {
+    let data = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    inspect_pe(&data, arch)
+}
+
+fn inspect_pe(data: &[u8], arch: Option<Arch>) -> Result<String> {
+    if data.len() < 0x40 || &data[..2] != b"MZ" {
+        bail!("no MZ header");
+    }
+
+    // Offsets below come from the file, so anything added to one of them is checked;
+    // once the signature is found, the fixed header offsets past it stay in range.
+    let pe = u32_at(data, 0x3c)? as usize;
+    let signature = pe.checked_add(4).and_then(|end| data.get(pe..end));
+    if signature != Some(b"PE\0\0".as_slice()) {
+        bail!("no PE signature at offset {pe:#x}");
+    }
+    let machine = u16_at(data, pe + 4)?;
+    if let Some(arch) = arch
+        && machine != arch.pe_machine()
+    {
+        bail!("built for {}, expected {arch}", machine_name(machine));
+    }
+    let sections = u16_at(data, pe + 6)? as usize;
+    let optional_size = u16_at(data, pe + 20)? as usize;
+
+    let optional = pe + 24;
+    let format = match u16_at(data, optional)? {
+        0x10b => "PE32",
+        0x20b => "PE32+",
+        magic => bail!("unknown optional header magic {magic:#x}"),
+    };
+    // The subsystem field sits at the same offset in PE32 and PE32+ optional headers.
+    let subsystem = match u16_at(data, optional + 68)? {
+        10 => "EFI application",
+        11 => "EFI boot service driver",
+        12 => "EFI runtime driver",
+        other => bail!("subsystem {other} is not an EFI image"),
+    };
+
+    let table = optional + optional_size;
+    for index in 0..sections {
+        let header = table + index * 40;
+        let size = u32_at(data, header + 16)? as usize;
+        let offset = u32_at(data, header + 20)? as usize;
+        match offset.checked_add(size) {
+            Some(end) if end <= data.len() => {}
+            Some(end) => bail!(
+                "section {index} ends at {end:#x} but the file is only {:#x} bytes (truncated?)",
+                data.len()
+            ),
+            None => bail!("section {index} offset {offset:#x} + size {size:#x} overflows"),
+        }
+    }
+
+    Ok(format!(
+        "{format} {} {subsystem}, {sections} section(s)",
+        machine_name(machine)
+    ))
+}
+
+pub fn machine_name(machine: u16) -> String {
+    match machine {
+        0x8664 => "x86_64".to_string(),
+        0xaa64 => "aarch64".to_string(),
+        0x14c => "i386".to_string(),
+        other => format!("machine {other:#x}"),
+    }
+}
+
+fn check_iso(path: &Path, contains: &[String]) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    inspect_iso(&mut file, contains)
+}
+
+fn inspect_iso<R: Read + Seek>(file: &mut R, contains: &[String]) -> Result<String> {
+    let len = file
+        .seek(SeekFrom::End(0))
+        .context("failed to find the end of the ISO")?;
+
+    let mut primary = None;
+    let mut boot_catalog = None;
+    for sector in 16.. {
+        let descriptor = read_at(file, sector * ISO_SECTOR, ISO_SECTOR as usize)
+            .context("no ISO 9660 volume descriptor set terminator")?;
+        if &descriptor[1..6] != b"CD001" {
+            bail!("sector {sector} is not an ISO 9660 volume descriptor");
+        }
+        match descriptor[0] {
+            0 if descriptor[7..].starts_with(b"EL TORITO SPECIFICATION") => {
+                boot_catalog = Some(u32_at(&descriptor, 71)?);
+            }
+            1 => primary = Some(descriptor),
+            255 => break,
+            _ => {}
+        }
+    }
+    let primary = primary.context("no ISO 9660 primary volume descriptor")?;
+
+    let blocks = u32_at(&primary, 80)? as u64;
+    let block_size = u16_at(&primary, 128)? as u64;
+    if blocks * block_size > len {
+        bail!(
+            "volume is {} bytes but the file is only {len} (truncated?)",
+            blocks * block_size
+        );
+    }
+
+    let catalog = boot_catalog.context("no El Torito boot record")?;
+    let catalog = read_at(file, catalog as u64 * ISO_SECTOR, ISO_SECTOR as usize)
+        .context("failed to read El Torito boot catalog")?;
+    if !has_efi_boot_entry(&catalog) {
+        bail!("El Torito boot catalog has no bootable EFI entry");
+    }
+
+    for wanted in contains {
+        if !iso_path_exists(file, &primary, wanted)? {
+            bail!("ISO is missing {wanted}");
+        }
+    }
+
+    Ok(format!(
+        "ISO 9660 with El Torito EFI boot entry{}",
+        if contains.is_empty() {
+            String::new()
+        } else {
+            format!(", {} present", contains.join(", "))
+        }
+    ))
+}
+
+/// Walks the boot catalog: a validation entry and default entry, then sections of entries,
+/// each with its own platform ID.
+fn has_efi_boot_entry(catalog: &[u8]) -> bool {
+    if catalog[0] != 1 || catalog[30..32] != [0x55, 0xaa] {
+        return false;
+    }
+    if catalog[1] == EL_TORITO_EFI && catalog[32] == EL_TORITO_BOOTABLE {
+        return true;
+    }
+
+    let mut offset = 64;
+    while offset + 32 <= catalog.len() {
+        let header = &catalog[offset..offset + 32];
+        if header[0] != 0x90 && header[0] != 0x91 {
+            break;
+        }
+        let platform = header[1];
+        let entries = u16::from_le_bytes([header[2], header[3]]) as usize;
+        for entry in 1..=entries {
+            let start = offset + entry * 32;
+            if start < catalog.len()
+                && platform == EL_TORITO_EFI
+                && catalog[start] == EL_TORITO_BOOTABLE
+            {
+                return true;
+            }
+        }
+        if header[0] == 0x91 {
+            break;
+        }
+        offset += (entries + 1) * 32;
+    }
+    false
+}
+
+/// Looks `path` up in the ISO 9660 directory tree (names compare case-insensitively,
+/// without `;1` version suffixes).
+fn iso_path_exists<R: Read + Seek>(file: &mut R, primary: &[u8], path: &str) -> Result<bool> {
+    let root = &primary[156..156 + 34];
+    let mut extent = u32_at(root, 2)?;
+    let mut size = u32_at(root, 10)?;
+
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    for (index, component) in components.iter().enumerate() {
+        if size > ISO_MAX_DIRECTORY_SIZE {
+            bail!("ISO directory for {path} claims {size} bytes (limit {ISO_MAX_DIRECTORY_SIZE})");
+        }
+        let directory = read_at(file, extent as u64 * ISO_SECTOR, size as usize)
+            .with_context(|| format!("failed to read ISO directory for {path}"))?;
+        let is_last = index + 1 == components.len();
+
+        let mut found = None;
+        let mut offset = 0;
+        while offset < directory.len() {
+            let record_len = directory[offset] as usize;
+            if record_len == 0 {
+                // Records don't cross sector boundaries; the rest of this sector is padding.
+                offset = (offset / ISO_SECTOR as usize + 1) * ISO_SECTOR as usize;
+                continue;
+            }
+            if record_len < ISO_MIN_RECORD_LEN {
+                bail!("malformed ISO directory record at offset {offset} ({record_len} bytes)");
+            }
+            let record = directory
+                .get(offset..offset + record_len)
+                .context("truncated ISO directory record")?;
+            let name_len = record[32] as usize;
+            let name = String::from_utf8_lossy(record.get(33..33 + name_len).unwrap_or_default());
+            let name = name
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches('.');
+            let is_dir = record[25] & 0x02 != 0;
+            if name.eq_ignore_ascii_case(component) && (is_last || is_dir) {
+                found = Some((u32_at(record, 2)?, u32_at(record, 10)?));
+                break;
+            }
+            offset += record_len;
+        }
+
+        match found {
+            Some((next_extent, next_size)) => {
+                extent = next_extent;
+                size = next_size;
+            }
+            None => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// Reads `len` bytes at `offset`, refusing reads past the end before allocating for them.
+fn read_at<R: Read + Seek>(file: &mut R, offset: u64, len: usize) -> Result<Vec<u8>> {
+    let end = file.seek(SeekFrom::End(0))?;
+    if offset.checked_add(len as u64).is_none_or(|stop| stop > end) {
+        bail!("{len} bytes at offset {offset} run past the end of the file ({end} bytes)");
+    }
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)
+        .with_context(|| format!("failed to read {len} bytes at offset {offset}"))?;
+    Ok(buf)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = offset
+        .checked_add(2)
+        .and_then(|end| data.get(offset..end))
+        .with_context(|| format!("file ends before offset {offset:#x}"))?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = offset
+        .checked_add(4)
+        .and_then(|end| data.get(offset..end))
+        .with_context(|| format!("file ends before offset {offset:#x}"))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    const PE_OFFSET: usize = 0x40;
+    const OPTIONAL: usize = PE_OFFSET + 24;
+    const OPTIONAL_SIZE: usize = 0xf0;
+    const SECTIONS: usize = OPTIONAL + OPTIONAL_SIZE;
+
+    /// A 1 KiB PE32+ image with one section at `section_offset..+section_size`.
+    fn pe_image(machine: u16, subsystem: u16, section_offset: u32, section_size: u32) -> Vec<u8> {
+        let mut data = vec![0; 0x400];
+        data[..2].copy_from_slice(b"MZ");
+        data[0x3c..0x40].copy_from_slice(&(PE_OFFSET as u32).to_le_bytes());
+        data[PE_OFFSET..PE_OFFSET + 4].copy_from_slice(b"PE\0\0");
+        data[PE_OFFSET + 4..PE_OFFSET + 6].copy_from_slice(&machine.to_le_bytes());
+        data[PE_OFFSET + 6..PE_OFFSET + 8].copy_from_slice(&1u16.to_le_bytes());
+        data[PE_OFFSET + 20..PE_OFFSET + 22].copy_from_slice(&(OPTIONAL_SIZE as u16).to_le_bytes());
+        data[OPTIONAL..OPTIONAL + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        data[OPTIONAL + 68..OPTIONAL + 70].copy_from_slice(&subsystem.to_le_bytes());
+        data[SECTIONS + 16..SECTIONS + 20].copy_from_slice(&section_size.to_le_bytes());
+        data[SECTIONS + 20..SECTIONS + 24].copy_from_slice(&section_offset.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn pe_accepts_efi_application() {
+        let image = pe_image(0x8664, 10, 0x200, 0x200);
+        assert_eq!(
+            inspect_pe(&image, Some(Arch::X86_64)).unwrap(),
+            "PE32+ x86_64 EFI application, 1 section(s)"
+        );
+    }
+
+    #[test]
+    fn pe_rejects_truncated_section() {
+        let image = pe_image(0x8664, 10, 0x200, 0x400);
+        let err = inspect_pe(&image, None).unwrap_err().to_string();
+        assert!(err.contains("truncated"), "{err}");
+    }
+
+    #[test]
+    fn iso_rejects_short_directory_record() {
+        let mut image = iso_image(&catalog(EL_TORITO_EFI));
+        image[ROOT_SECTOR * SECTOR] = 20;
+        let err =
+            inspect_iso(&mut Cursor::new(image), &paths(&["EFI/BOOT/BOOTX64.EFI"])).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("malformed ISO directory record"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn iso_rejects_oversized_directory() {
+        let mut image = iso_image(&catalog(EL_TORITO_EFI));
+        put_u32(&mut image, 16 * SECTOR + 156 + 10, u32::MAX);
+        let err =
+            inspect_iso(&mut Cursor::new(image), &paths(&["EFI/BOOT/BOOTX64.EFI"])).unwrap_err();
+        assert!(format!("{err:#}").contains("limit"), "{err:#}");
+    }
+
+    #[test]
+    fn pe_rejects_overflowing_section() {
+        let image = pe_image(0x8664, 10, u32::MAX, u32::MAX);
+        assert!(inspect_pe(&image, None).is_err());
+    }
+
+    #[test]
+    fn pe_rejects_wrong_machine() {
+        let image = pe_image(0x8664, 10, 0x200, 0x200);
+        let err = inspect_pe(&image, Some(Arch::Aarch64))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "built for x86_64, expected aarch64");
+    }
+
+    #[test]
+    fn pe_rejects_non_efi_subsystem() {
+        let image = pe_image(0x8664, 3, 0x200, 0x200);
+        let err = inspect_pe(&image, None).unwrap_err().to_string();
+        assert!(err.contains("not an EFI image"), "{err}");
+    }
+
+    #[test]
+    fn pe_rejects_signature_offset_past_end() {
+        let mut image = pe_image(0x8664, 10, 0x200, 0x200);
+        image[0x3c..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = inspect_pe(&image, None).unwrap_err().to_string();
+        assert!(err.contains("no PE signature"), "{err}");
+    }
+
+    const SECTOR: usize = ISO_SECTOR as usize;
+    const CATALOG_SECTOR: usize = 19;
+    const ROOT_SECTOR: usize = 20;
+    const ISO_SECTORS: usize = 23;
+
+    fn put_u32(buf: &mut [u8], offset: usize, value: u32) {
+        buf[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn descriptor(image: &mut [u8], sector: usize, kind: u8) -> &mut [u8] {
+        let descriptor = &mut image[sector * SECTOR..(sector + 1) * SECTOR];
+        descriptor[0] = kind;
+        descriptor[1..6].copy_from_slice(b"CD001");
+        descriptor[6] = 1;
+        descriptor
+    }
+
+    /// Appends a directory record for `name` at `extent` to `dir`, returning the next offset.
+    fn dir_record(dir: &mut [u8], offset: usize, name: &[u8], extent: u32, is_dir: bool) -> usize {
+        let len = 33 + name.len() + (name.len() + 1) % 2;
+        let record = &mut dir[offset..offset + len];
+        record[0] = len as u8;
+        put_u32(record, 2, extent);
+        put_u32(record, 10, SECTOR as u32);
+        record[25] = if is_dir { 0x02 } else { 0 };
+        record[32] = name.len() as u8;
+        record[33..33 + name.len()].copy_from_slice(name);
+        offset + len
+    }
+
+    /// An ISO with `EFI/BOOT/BOOTX64.EFI` and the given El Torito boot catalog.
+    fn iso_image(catalog: &[u8]) -> Vec<u8> {
+        let mut image = vec![0; ISO_SECTORS * SECTOR];
+
+        let primary = descriptor(&mut image, 16, 1);
+        put_u32(primary, 80, ISO_SECTORS as u32);
+        primary[128..130].copy_from_slice(&(SECTOR as u16).to_le_bytes());
+        dir_record(&mut primary[156..190], 0, &[0], ROOT_SECTOR as u32, true);
+
+        let boot = descriptor(&mut image, 17, 0);
+        boot[7..30].copy_from_slice(b"EL TORITO SPECIFICATION");
+        put_u32(boot, 71, CATALOG_SECTOR as u32);
+
+        descriptor(&mut image, 18, 255);
+
+        let at = CATALOG_SECTOR * SECTOR;
+        image[at..at + catalog.len()].copy_from_slice(catalog);
+
+        for (sector, name, child, is_dir) in [
+            (ROOT_SECTOR, b"EFI".as_slice(), 21, true),
+            (21, b"BOOT".as_slice(), 22, true),
+            (22, b"BOOTX64.EFI;1".as_slice(), 0, false),
+        ] {
+            let dir = &mut image[sector * SECTOR..(sector + 1) * SECTOR];
+            let next = dir_record(dir, 0, &[0], sector as u32, true);
+            let next = dir_record(dir, next, &[1], ROOT_SECTOR as u32, true);
+            dir_record(dir, next, name, child, is_dir);
+        }
+        image
+    }
+
+    /// Validation entry for `platform` followed by a bootable default entry.
+    fn catalog(platform: u8) -> Vec<u8> {
+        let mut catalog = vec![0; 64];
+        catalog[0] = 1;
+        catalog[1] = platform;
+        catalog[30..32].copy_from_slice(&[0x55, 0xaa]);
+        catalog[32] = EL_TORITO_BOOTABLE;
+        catalog
+    }
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn iso_accepts_default_efi_entry_and_finds_paths() {
+        let mut image = Cursor::new(iso_image(&catalog(EL_TORITO_EFI)));
+        let summary = inspect_iso(&mut image, &paths(&["EFI/BOOT/BOOTX64.EFI"])).unwrap();
+        assert!(
+            summary.contains("EFI/BOOT/BOOTX64.EFI present"),
+            "{summary}"
+        );
+    }
+
+    #[test]
+    fn iso_reports_missing_path() {
+        let mut image = Cursor::new(iso_image(&catalog(EL_TORITO_EFI)));
+        let err = inspect_iso(&mut image, &paths(&["EFI/BOOT/BOOTAA64.EFI"]))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "ISO is missing EFI/BOOT/BOOTAA64.EFI");
+    }
+
+    #[test]
+    fn iso_accepts_efi_entry_in_section_header() {
+        // BIOS default entry, then a final section header for one EFI entry.
+        let mut catalog = catalog(0);
+        catalog.resize(128, 0);
+        catalog[64] = 0x91;
+        catalog[65] = EL_TORITO_EFI;
+        catalog[66..68].copy_from_slice(&1u16.to_le_bytes());
+        catalog[96] = EL_TORITO_BOOTABLE;
+        assert!(has_efi_boot_entry(&catalog));
+
+        let mut image = Cursor::new(iso_image(&catalog));
+        inspect_iso(&mut image, &[]).unwrap();
+    }
+
+    #[test]
+    fn iso_rejects_bios_only_catalog() {
+        assert!(!has_efi_boot_entry(&catalog(0)));
+
+        let mut image = Cursor::new(iso_image(&catalog(0)));
+        let err = inspect_iso(&mut image, &[]).unwrap_err().to_string();
+        assert_eq!(err, "El Torito boot catalog has no bootable EFI entry");
+    }
+
+    #[test]
+    fn iso_rejects_missing_terminator() {
+        let mut image = iso_image(&catalog(EL_TORITO_EFI));
+        image.truncate(18 * SECTOR);
+        let err = inspect_iso(&mut Cursor::new(image), &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no ISO 9660 volume descriptor set terminator"
+        );
+    }
+
+    #[test]
+    fn iso_rejects_truncated_volume() {
+        let mut image = iso_image(&catalog(EL_TORITO_EFI));
+        image.truncate((ISO_SECTORS - 1) * SECTOR);
+        let err = inspect_iso(&mut Cursor::new(image), &[])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("truncated"), "{err}");
+    }
+
+    fn tar_gz(entries: &[&str]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for name in entries {
+            let body = vec![b'x'; 4096];
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, body.as_slice())
+                .unwrap();
+        }
+        let mut encoder = builder.into_inner().unwrap();
+        encoder.flush().unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn tar_gz_matches_entries_by_name_or_path() {
+        let archive = tar_gz(&["./bin/truthdb", "README.md"]);
+        inspect_tar_gz(archive.as_slice(), &paths(&["truthdb", "bin/truthdb"])).unwrap();
+        let err = inspect_tar_gz(archive.as_slice(), &paths(&["truthdb-installer"]))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "archive is missing truthdb-installer");
+    }
+
+    #[test]
+    fn tar_gz_rejects_truncated_archive() {
+        let archive = tar_gz(&["truthdb"]);
+        let truncated = &archive[..archive.len() / 2];
+        assert!(inspect_tar_gz(truncated, &[]).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_SHA: &str = env!("ORCHESTRATOR_GIT_SHA");
+pub const BUILD_DATE: &str = env!("ORCHESTRATOR_BUILD_DATE");
+pub const MANIFEST_SHA256: &str = env!("ORCHESTRATOR_MANIFEST_SHA256");
+
+/// `--version` string, e.g. `0.1.0 (1a2b3c4d5e6f)` or `0.1.0 (1a2b3c4d5e6f-dirty)`.
+pub const LONG_VERSION: &str = if git_dirty() {
+    concat!(
+        env!("CARGO_PKG_VERSION"),
+        " (",
+        env!("ORCHESTRATOR_GIT_SHA"),
+        "-dirty)"
+    )
+} else {
+    concat!(
+        env!("CARGO_PKG_VERSION"),
+        " (",
+        env!("ORCHESTRATOR_GIT_SHA"),
+        ")"
+    )
+};
+
+pub const fn git_dirty() -> bool {
+    matches!(env!("ORCHESTRATOR_GIT_DIRTY").as_bytes(), b"true")
+}
+
+/// Metadata embedded at build time by `build.rs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub git_dirty: bool,
+    pub build_date: &'static str,
+    pub workspace_manifest_sha256: &'static str,
+}
+
+pub fn current() -> BuildInfo {
+    BuildInfo {
+        version: VERSION,
+        git_sha: GIT_SHA,
+        git_dirty: git_dirty(),
+        build_date: BUILD_DATE,
+        workspace_manifest_sha256: MANIFEST_SHA256,
+    }
+}
+
+impl BuildInfo {
+    pub fn render_plain(&self) -> String {
+        format!(
+            "orchestrator {}\ngit sha:         {}{}\nbuild date:      {}\nmanifest sha256: {}",
+            self.version,
+            self.git_sha,
+            if self.git_dirty { " (dirty)" } else { "" },
+            self.build_date,
+            self.workspace_manifest_sha256
+        )
+    }
+}
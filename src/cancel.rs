@@ -0,0 +1,95 @@
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+/// How often sleeping commands wake up to check for cancellation.
+const CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// Cooperative cancellation for long-running commands; the TUI cancels it on quit.
+///
+/// Commands check it between steps and while polling, then stop with [`Cancelled`].
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+/// Error for a command stopped by its [`CancelToken`].
+#[derive(Debug)]
+pub struct Cancelled {
+    pub stage: String,
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cancelled at stage {}", self.stage)
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Fails with [`Cancelled`] at `stage` if cancellation was requested.
+    pub fn check(&self, stage: &str) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Cancelled {
+                stage: stage.to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Sleeps for `duration`, failing with [`Cancelled`] at `stage` as soon as it's requested.
+    pub fn sleep(&self, duration: Duration, stage: &str) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        loop {
+            self.check(stage)?;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            std::thread::sleep(remaining.min(CANCEL_POLL));
+        }
+    }
+}
+
+/// The [`Cancelled`] error somewhere in `err`'s chain, if any.
+pub fn cancelled(err: &anyhow::Error) -> Option<&Cancelled> {
+    err.downcast_ref::<Cancelled>()
+}
+
+/// A reader that stops with an I/O error once `cancel` is cancelled, so long copies end
+/// promptly.
+pub struct CancelReader<R> {
+    inner: R,
+    cancel: CancelToken,
+}
+
+impl<R> CancelReader<R> {
+    pub fn new(inner: R, cancel: CancelToken) -> Self {
+        Self { inner, cancel }
+    }
+}
+
+impl<R: Read> Read for CancelReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(std::io::Error::other("cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
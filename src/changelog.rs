@@ -0,0 +1,231 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::git::{Commit, Repo};
+
+/// Section order for conventional-commit types; anything else lands in "Other".
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("Features", &["feat"]),
+    ("Bug Fixes", &["fix"]),
+    ("Performance", &["perf"]),
+    ("Refactoring", &["refactor"]),
+    ("Documentation", &["docs"]),
+    ("Tests", &["test"]),
+    ("Build & CI", &["build", "ci"]),
+    ("Chores", &["chore", "style", "revert"]),
+];
+
+/// Commits going into a release of one repo.
+#[derive(Debug, Clone)]
+pub struct Changes {
+    pub previous_tag: Option<String>,
+    pub commits: Vec<Commit>,
+}
+
+impl Changes {
+    /// Collects commits between the previous release tag (below `version`) and `commit`.
+    pub fn collect(repo: &Repo, version: &semver::Version, commit: &str) -> Result<Self> {
+        let previous_tag = repo.previous_release_tag(version, commit)?;
+        let commits = repo.commits_between(previous_tag.as_deref(), commit)?;
+        Ok(Self {
+            previous_tag,
+            commits,
+        })
+    }
+}
+
+/// Splits `type(scope)!: description` into its type, scope, and description.
+fn parse_conventional(subject: &str) -> Option<(&str, Option<&str>, &str)> {
+    let (head, description) = subject.split_once(": ")?;
+    let head = head.strip_suffix('!').unwrap_or(head);
+    let (kind, scope) = match head.split_once('(') {
+        Some((kind, rest)) => (kind, Some(rest.strip_suffix(')')?)),
+        None => (head, None),
+    };
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some((kind, scope, description.trim()))
+}
+
+/// Renders commit subjects as bullet lines, grouped under headings when any of them
+/// use conventional-commit prefixes.
+///
+/// `heading` formats a section title, so callers can render plain text or Markdown.
+pub fn render_grouped(commits: &[Commit], heading: impl Fn(&str) -> String) -> Vec<String> {
+    let bullet = |c: &Commit, text: &str| format!("- {text} ({})", &c.sha[..c.sha.len().min(7)]);
+
+    if !commits
+        .iter()
+        .any(|c| parse_conventional(&c.subject).is_some())
+    {
+        return commits.iter().map(|c| bullet(c, &c.subject)).collect();
+    }
+
+    let mut lines = Vec::new();
+    let mut emit = |title: &str, items: Vec<String>| {
+        if items.is_empty() {
+            return;
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(heading(title));
+        lines.extend(items);
+    };
+
+    for (title, kinds) in SECTIONS {
+        let items = commits
+            .iter()
+            .filter_map(|c| {
+                let (kind, scope, description) = parse_conventional(&c.subject)?;
+                if !kinds.contains(&kind) {
+                    return None;
+                }
+                Some(match scope {
+                    Some(scope) => bullet(c, &format!("{scope}: {description}")),
+                    None => bullet(c, description),
+                })
+            })
+            .collect();
+        emit(title, items);
+    }
+
+    let other = commits
+        .iter()
+        .filter(|c| {
+            parse_conventional(&c.subject)
+                .is_none_or(|(kind, _, _)| !SECTIONS.iter().any(|(_, ks)| ks.contains(&kind)))
+        })
+        .map(|c| bullet(c, &c.subject))
+        .collect();
+    emit("Other", other);
+
+    lines
+}
+
+/// Annotated tag message: a `Release vX` title followed by the generated changelog.
+pub fn render_tag_message(tag: &str, changes: &Changes) -> String {
+    let mut out = format!("Release {tag}\n\n");
+    match &changes.previous_tag {
+        Some(previous) => out.push_str(&format!("Changes since {previous}:\n\n")),
+        None => out.push_str("Changes:\n\n"),
+    }
+
+    if changes.commits.is_empty() {
+        out.push_str("- no changes\n");
+        return out;
+    }
+
+    for line in render_grouped(&changes.commits, |title| format!("{title}:")) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Opens `$VISUAL`/`$EDITOR` on `message` and returns the edited text.
+///
+/// Lines starting with `#` are dropped; an empty result aborts.
+pub fn edit_message(repo_name: &str, message: &str) -> Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    let path = env::temp_dir().join(format!(
+        "orchestrator-tag-{}-{}.txt",
+        repo_name,
+        std::process::id()
+    ));
+    let template = format!(
+        "{message}\n# Tag message for {repo_name}. Lines starting with '#' are ignored.\n# Save an empty message to abort.\n"
+    );
+    fs::write(&path, template).with_context(|| format!("failed to write {}", path.display()))?;
+
+    // Run through the shell so EDITOR values with arguments (e.g. "code --wait") work.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status()
+        .with_context(|| format!("failed to launch editor '{editor}'"));
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status?;
+    if !status.success() {
+        bail!("editor '{editor}' exited with {status}; aborting");
+    }
+
+    let edited = edited.context("failed to read edited tag message")?;
+    let message = edited
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string();
+    if message.is_empty() {
+        bail!("empty tag message for {repo_name}; aborting");
+    }
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(sha: &str, subject: &str) -> Commit {
+        Commit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_conventional_subjects() {
+        assert_eq!(
+            parse_conventional("feat(iso): add aarch64 image"),
+            Some(("feat", Some("iso"), "add aarch64 image"))
+        );
+        assert_eq!(
+            parse_conventional("fix!: drop legacy flag"),
+            Some(("fix", None, "drop legacy flag"))
+        );
+        assert_eq!(parse_conventional("Merge branch 'main'"), None);
+        assert_eq!(parse_conventional("Fix: capitalized type"), None);
+        assert_eq!(parse_conventional("feat(iso: unclosed scope"), None);
+    }
+
+    #[test]
+    fn groups_tag_message_by_type() {
+        let changes = Changes {
+            previous_tag: Some("v1.2.2".to_string()),
+            commits: vec![
+                commit("aaaaaaaaaa", "fix(kernel): boot on arm"),
+                commit("bbbbbbbbbb", "feat: multi-arch ISOs"),
+                commit("cccccccccc", "bump deps"),
+            ],
+        };
+        assert_eq!(
+            render_tag_message("v1.2.3", &changes),
+            "Release v1.2.3\n\nChanges since v1.2.2:\n\n\
+             Features:\n- multi-arch ISOs (bbbbbbb)\n\n\
+             Bug Fixes:\n- kernel: boot on arm (aaaaaaa)\n\n\
+             Other:\n- bump deps (ccccccc)\n"
+        );
+    }
+
+    #[test]
+    fn plain_subjects_stay_ungrouped() {
+        let commits = [commit("aaaaaaaaaa", "Update README")];
+        assert_eq!(
+            render_grouped(&commits, |title| title.to_string()),
+            ["- Update README (aaaaaaa)"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::Path;
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+/// SHA-256 of a file's contents, read in a streaming fashion.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(hex(&hasher.finalize()))
+}
+
+/// Lowercase hex encoding of a digest.
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Extracts the digest for `file_name` from `sha256sum`-style output.
+///
+/// A file containing a single bare digest is accepted as well.
+pub fn parse_sha256_file(text: &str, file_name: &str) -> Option<String> {
+    let mut bare = None;
+    for line in text.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(digest) = parts.next() else {
+            continue;
+        };
+        if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+
+        match parts.next() {
+            Some(name) if name.trim_start_matches('*') == file_name => {
+                return Some(digest.to_lowercase());
+            }
+            Some(_) => {}
+            None => bare = Some(digest.to_lowercase()),
+        }
+    }
+    bare
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    #[test]
+    fn hashes_bytes() {
+        assert_eq!(sha256_hex(b"abc"), ABC);
+    }
+
+    #[test]
+    fn hashes_files() {
+        let path = std::env::temp_dir().join(format!("checksum-test-{}", std::process::id()));
+        std::fs::write(&path, "abc").unwrap();
+        let digest = sha256_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(digest.unwrap(), ABC);
+    }
+
+    #[test]
+    fn parses_sha256sum_output() {
+        let other = "0".repeat(64);
+        let text = format!(
+            "{other}  other.tar.gz\n{}  truthdb.tar.gz\n",
+            ABC.to_uppercase()
+        );
+        assert_eq!(
+            parse_sha256_file(&text, "truthdb.tar.gz").as_deref(),
+            Some(ABC)
+        );
+        // Binary-mode entries are prefixed with `*`.
+        assert_eq!(
+            parse_sha256_file(&format!("{ABC} *BOOTX64.EFI"), "BOOTX64.EFI").as_deref(),
+            Some(ABC)
+        );
+        assert_eq!(parse_sha256_file(&text, "missing.tar.gz"), None);
+    }
+
+    #[test]
+    fn accepts_a_bare_digest() {
+        assert_eq!(
+            parse_sha256_file(&format!("{ABC}\n"), "anything").as_deref(),
+            Some(ABC)
+        );
+        assert_eq!(parse_sha256_file("not a digest  file", "file"), None);
+    }
+}
@@ -0,0 +1,28 @@
+//! Calendar dates for Unix timestamps. Shared with `build.rs`, so it only uses `std`.
+
+/// `(year, month, day)` in UTC of a Unix timestamp (Howard Hinnant's civil-from-days).
+pub fn civil_date(epoch: u64) -> (i64, i64, i64) {
+    let z = (epoch / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_known_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(951_782_400), (2000, 2, 29));
+        assert_eq!(civil_date(951_868_799), (2000, 2, 29));
+        assert_eq!(civil_date(1_735_689_600), (2025, 1, 1));
+    }
+}
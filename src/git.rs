@@ -1,8 +1,30 @@
+use crate::git_backend::backend;
 use anyhow::{Context, Result, bail};
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 
-fn run_git(repo_dir: &Path, args: &[&str]) -> Result<String> {
+const CLONE_ERROR_TAIL_LINES: usize = 20;
+
+/// Highest of `tags` that sorts below `version`.
+///
+/// A final release skips prereleases, so `v1.2.3` follows `v1.2.2` rather than its own
+/// `v1.2.3-rc.N`; a prerelease follows whatever came just before it.
+pub fn previous_release<'a>(
+    version: &semver::Version,
+    tags: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    tags.into_iter()
+        .filter_map(|tag| {
+            let parsed = semver::Version::parse(tag.strip_prefix('v')?).ok()?;
+            let candidate = parsed < *version && (!version.pre.is_empty() || parsed.pre.is_empty());
+            candidate.then_some((parsed, tag))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag.to_string())
+}
+
+pub fn run_git(repo_dir: &Path, args: &[&str]) -> Result<String> {
     let output = Command::new("git")
         .current_dir(repo_dir)
         .args(args)
@@ -24,7 +46,7 @@ fn run_git(repo_dir: &Path, args: &[&str]) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-fn run_git_status(repo_dir: &Path, args: &[&str]) -> Result<(i32, String, String)> {
+pub fn run_git_status(repo_dir: &Path, args: &[&str]) -> Result<(i32, String, String)> {
     let output = Command::new("git")
         .current_dir(repo_dir)
         .args(args)
@@ -37,11 +59,39 @@ fn run_git_status(repo_dir: &Path, args: &[&str]) -> Result<(i32, String, String
     Ok((code, stdout, stderr))
 }
 
-pub fn clone_repo(root_dir: &Path, url: &str, name: &str) -> Result<()> {
-    let output = Command::new("git")
+#[derive(Clone, Debug, Default)]
+pub struct CloneOptions {
+    /// Create a shallow clone with this many commits of history.
+    pub depth: Option<u32>,
+    /// Partial clone filter spec, e.g. `blob:none`.
+    pub filter: Option<String>,
+}
+
+/// Clones `url` into `root_dir/name`, forwarding git's progress lines to `on_progress`.
+pub fn clone_repo(
+    root_dir: &Path,
+    url: &str,
+    name: &str,
+    options: &CloneOptions,
+    mut on_progress: impl FnMut(&str),
+) -> Result<()> {
+    let mut args = vec!["clone".to_string(), "--progress".to_string()];
+    if let Some(depth) = options.depth {
+        args.push(format!("--depth={depth}"));
+    }
+    if let Some(filter) = &options.filter {
+        args.push(format!("--filter={filter}"));
+    }
+    args.push(url.to_string());
+    args.push(name.to_string());
+
+    let mut child = Command::new("git")
         .current_dir(root_dir)
-        .args(["clone", url, name])
-        .output()
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
         .with_context(|| {
             format!(
                 "failed to run git clone for {url} in {}",
@@ -49,22 +99,80 @@ pub fn clone_repo(root_dir: &Path, url: &str, name: &str) -> Result<()> {
             )
         })?;
 
-    if !output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
+    // git rewrites progress lines in place with '\r', so split on both line endings.
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("git clone stderr not captured")?;
+    let mut tail: Vec<String> = Vec::new();
+    let mut line = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stderr
+            .read(&mut buf)
+            .context("failed to read git clone output")?;
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            if b == b'\r' || b == b'\n' {
+                finish_clone_line(&mut line, &mut tail, &mut on_progress);
+            } else {
+                line.push(b);
+            }
+        }
+    }
+    finish_clone_line(&mut line, &mut tail, &mut on_progress);
+
+    let status = child.wait().context("failed to wait for git clone")?;
+    if !status.success() {
         bail!(
-            "git clone failed for {} into {} (exit={}):\nstdout:\n{}\nstderr:\n{}",
+            "git clone failed for {} into {} (exit={}):\n{}",
             url,
             root_dir.display(),
-            output.status,
-            stdout.trim_end(),
-            stderr.trim_end()
+            status,
+            tail.join("\n")
         );
     }
 
     Ok(())
 }
 
+/// Reports a completed clone output line and keeps it in the last
+/// `CLONE_ERROR_TAIL_LINES` lines shown if the clone fails.
+fn finish_clone_line(
+    line: &mut Vec<u8>,
+    tail: &mut Vec<String>,
+    on_progress: &mut impl FnMut(&str),
+) {
+    let text = String::from_utf8_lossy(line).trim().to_string();
+    line.clear();
+    if text.is_empty() {
+        return;
+    }
+    on_progress(&text);
+    if tail.len() == CLONE_ERROR_TAIL_LINES {
+        tail.remove(0);
+    }
+    tail.push(text);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TagOptions {
+    /// Create a GPG/SSH-signed tag (`git tag -s`) instead of a plain annotated one.
+    pub sign: bool,
+    /// Tag message; defaults to `Release {tag}`.
+    pub message: Option<String>,
+    /// Commit to tag; defaults to HEAD.
+    pub target: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Commit {
+    pub sha: String,
+    pub subject: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct Repo {
     pub name: String,
@@ -82,12 +190,12 @@ impl Repo {
     }
 
     pub fn ensure_worktree_clean(&self) -> Result<()> {
-        let status = run_git(&self.dir, &["status", "--porcelain"])?;
+        let status = backend().status(&self.dir)?;
         if !status.is_empty() {
             bail!(
                 "{} has uncommitted changes:\n{}\n\nCommit/stash them before releasing.",
                 self.dir.display(),
-                status
+                status.join("\n")
             );
         }
         Ok(())
@@ -99,7 +207,42 @@ impl Repo {
     }
 
     pub fn head_commit(&self) -> Result<String> {
-        run_git(&self.dir, &["rev-parse", "HEAD"]).context("failed to resolve HEAD")
+        backend()
+            .resolve_commit(&self.dir, "HEAD")?
+            .with_context(|| format!("failed to resolve HEAD in {}", self.dir.display()))
+    }
+
+    /// Resolves `rev` to a full commit SHA, or `None` if it names nothing.
+    pub fn resolve_commit(&self, rev: &str) -> Result<Option<String>> {
+        backend().resolve_commit(&self.dir, rev)
+    }
+
+    /// Commit `origin/<branch>` points at after the last fetch.
+    pub fn remote_branch_commit(&self, branch: &str) -> Result<Option<String>> {
+        self.resolve_commit(&format!("refs/remotes/origin/{branch}"))
+    }
+
+    /// Branches on origin (as of the last fetch) whose history contains `commit`.
+    pub fn remote_branches_containing(&self, commit: &str) -> Result<Vec<String>> {
+        backend().remote_branches_containing(&self.dir, commit)
+    }
+
+    /// First line of `commit`'s message.
+    pub fn commit_subject(&self, commit: &str) -> Result<String> {
+        backend().commit_subject(&self.dir, commit)
+    }
+
+    /// Contents of `path` as of `commit`, without touching the checkout.
+    pub fn read_file_at(&self, commit: &str, path: &Path) -> Result<String> {
+        let path = path
+            .to_str()
+            .with_context(|| format!("non-UTF-8 path {}", path.display()))?;
+        backend().read_file_at(&self.dir, commit, path)
+    }
+
+    /// Returns whether `ancestor` is reachable from `descendant`.
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
+        backend().is_ancestor(&self.dir, ancestor, descendant)
     }
 
     pub fn ensure_on_branch_and_synced_to_origin(&self) -> Result<String> {
@@ -107,31 +250,24 @@ impl Repo {
         self.fetch_origin()?;
 
         // Fail if detached.
-        let (code, branch, _) =
-            run_git_status(&self.dir, &["symbolic-ref", "--quiet", "--short", "HEAD"])?;
-        if code != 0 || branch.is_empty() {
+        let Some(branch) = backend().current_branch(&self.dir)? else {
             bail!(
                 "{} is in detached HEAD state; check out a branch first.",
                 self.dir.display()
             );
-        }
+        };
 
-        let local_head = run_git(&self.dir, &["rev-parse", "HEAD"])?;
+        let local_head = self.head_commit()?;
         let remote_ref = format!("origin/{branch}");
-        let remote_head = run_git(&self.dir, &["rev-parse", &remote_ref])
+        let remote_head = backend()
+            .resolve_commit(&self.dir, &remote_ref)?
             .with_context(|| format!("failed to resolve {remote_ref} in {}", self.dir.display()))?;
 
         if local_head != remote_head {
-            let counts = run_git(
-                &self.dir,
-                &[
-                    "rev-list",
-                    "--left-right",
-                    "--count",
-                    &format!("HEAD...{remote_ref}"),
-                ],
-            )
-            .unwrap_or_default();
+            let counts = backend()
+                .ahead_behind(&self.dir, "HEAD", &remote_ref)
+                .map(|(ahead, behind)| format!("{ahead}\t{behind}"))
+                .unwrap_or_default();
             bail!(
                 "{} is not synced with {}.\nlocal HEAD:  {}\nremote HEAD: {}\n(diverged counts: {})\n\nPlease `git pull` / fast-forward your branch before tagging.",
                 self.dir.display(),
@@ -146,7 +282,7 @@ impl Repo {
     }
 
     pub fn ensure_origin_matches_expected(&self) -> Result<()> {
-        let url = run_git(&self.dir, &["remote", "get-url", "origin"])?;
+        let url = backend().remote_url(&self.dir, "origin")?;
         // Accept both SSH and HTTPS; just sanity-check that owner/repo appear.
         let needle = format!("{}/{}", self.owner, self.name);
         if !url.to_lowercase().contains(&needle.to_lowercase()) {
@@ -161,8 +297,7 @@ impl Repo {
     }
 
     pub fn ensure_tag_absent_local_and_remote(&self, tag: &str) -> Result<()> {
-        let local = run_git(&self.dir, &["tag", "-l", tag])?;
-        if !local.is_empty() {
+        if !backend().tags(&self.dir, tag, None)?.is_empty() {
             bail!("{} already has local tag {tag}", self.dir.display());
         }
 
@@ -191,18 +326,7 @@ impl Repo {
     }
 
     pub fn local_tag_commit(&self, tag: &str) -> Result<Option<String>> {
-        // `rev-parse --verify` exits non-zero if it doesn't exist.
-        let (code, _stdout, _stderr) = run_git_status(&self.dir, &["rev-parse", "--verify", tag])?;
-        if code != 0 {
-            return Ok(None);
-        }
-
-        // Resolve tag to the commit it ultimately points at.
-        let commit = run_git(&self.dir, &["rev-list", "-n", "1", tag])?;
-        if commit.is_empty() {
-            return Ok(None);
-        }
-        Ok(Some(commit))
+        backend().local_tag_commit(&self.dir, tag)
     }
 
     pub fn remote_tag_commit(&self, tag: &str) -> Result<Option<String>> {
@@ -226,14 +350,173 @@ impl Repo {
         Ok(Some(sha))
     }
 
-    pub fn create_annotated_tag(&self, tag: &str) -> Result<()> {
-        let msg = format!("Release {tag}");
-        let _ = run_git(&self.dir, &["tag", "-a", tag, "-m", &msg])?;
+    pub fn create_annotated_tag(&self, tag: &str, options: &TagOptions) -> Result<()> {
+        let msg = options
+            .message
+            .clone()
+            .unwrap_or_else(|| format!("Release {tag}"));
+        let mode = if options.sign { "-s" } else { "-a" };
+        let mut args = vec!["tag", mode, "--cleanup=verbatim", tag, "-m", &msg];
+        if let Some(target) = &options.target {
+            args.push(target);
+        }
+        let _ = run_git(&self.dir, &args)?;
         Ok(())
     }
 
+    /// The release `version`'s changelog starts from, among the `v{semver}` tags reachable
+    /// from `commit` (see [`previous_release`]).
+    pub fn previous_release_tag(
+        &self,
+        version: &semver::Version,
+        commit: &str,
+    ) -> Result<Option<String>> {
+        let tags = backend().tags(&self.dir, "v*", Some(commit))?;
+        Ok(previous_release(version, tags.iter().map(String::as_str)))
+    }
+
+    /// Every local `v{semver}` tag, in no particular order. Tags that aren't SemVer are ignored.
+    pub fn release_versions(&self) -> Result<Vec<semver::Version>> {
+        let tags = backend().tags(&self.dir, "v*", None)?;
+        Ok(tags
+            .iter()
+            .filter_map(|tag| semver::Version::parse(tag.strip_prefix('v')?).ok())
+            .collect())
+    }
+
+    /// Non-merge commits in `from..to` (or all of `to`'s history without `from`), newest first.
+    pub fn commits_between(&self, from: Option<&str>, to: &str) -> Result<Vec<Commit>> {
+        backend().commits_between(&self.dir, from, to)
+    }
+
+    /// Verifies the signature on `tag` with the locally configured keys.
+    pub fn verify_tag_signature(&self, tag: &str) -> Result<()> {
+        let (code, _stdout, stderr) = run_git_status(&self.dir, &["tag", "-v", tag])?;
+        if code != 0 {
+            bail!(
+                "{} tag {tag} failed signature verification (exit={code}):\n{stderr}",
+                self.dir.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Ensures git is configured to sign tags, returning a short description of the key.
+    ///
+    /// OpenPGP falls back to the committer identity when `user.signingkey` is unset, like
+    /// `git tag -s` does; SSH and X.509 need an explicit key. SSH signing also needs
+    /// `gpg.ssh.allowedSignersFile` so the tag can be verified locally before it is pushed.
+    pub fn ensure_signing_key_configured(&self) -> Result<String> {
+        let config = |key: &str| -> Result<Option<String>> {
+            let (code, stdout, stderr) = run_git_status(&self.dir, &["config", "--get", key])?;
+            match code {
+                0 if !stdout.is_empty() => Ok(Some(stdout)),
+                0 | 1 => Ok(None),
+                _ => bail!(
+                    "{} failed to read git config {key} (exit={code}): {stderr}",
+                    self.dir.display()
+                ),
+            }
+        };
+
+        let format = config("gpg.format")?.unwrap_or_else(|| "openpgp".to_string());
+        let key = match config("user.signingkey")? {
+            Some(key) => key,
+            None if format == "openpgp" => {
+                let (code, ident, stderr) =
+                    run_git_status(&self.dir, &["var", "GIT_COMMITTER_IDENT"])?;
+                if code != 0 {
+                    bail!(
+                        "{} has no signing key configured (user.signingkey) and no committer identity to fall back to: {stderr}",
+                        self.dir.display()
+                    );
+                }
+                // "Name <email> timestamp tz": gpg picks the key by the name and email part.
+                let ident = ident.rsplitn(3, ' ').last().unwrap_or(&ident).to_string();
+                format!("committer identity {ident}")
+            }
+            None => bail!(
+                "{} signs tags with {format} but has no signing key configured (user.signingkey). Configure one or drop --sign.",
+                self.dir.display()
+            ),
+        };
+
+        if format == "ssh" && config("gpg.ssh.allowedSignersFile")?.is_none() {
+            bail!(
+                "{} uses SSH tag signing but gpg.ssh.allowedSignersFile is not set, so signed tags can't be verified locally.",
+                self.dir.display()
+            );
+        }
+
+        Ok(format!("{format} key {key}"))
+    }
+
+    /// Stages `paths` (relative to the repo) and commits only them with `message`.
+    pub fn commit_paths(&self, paths: &[PathBuf], message: &str) -> Result<String> {
+        let mut add = vec!["add", "--"];
+        let mut commit = vec!["commit", "--quiet", "-m", message, "--"];
+        for path in paths {
+            let path = path
+                .to_str()
+                .with_context(|| format!("non-UTF-8 path {}", path.display()))?;
+            add.push(path);
+            commit.push(path);
+        }
+        run_git(&self.dir, &add)?;
+        run_git(&self.dir, &commit)?;
+        self.head_commit()
+    }
+
     pub fn push_tag(&self, tag: &str) -> Result<()> {
         let _ = run_git(&self.dir, &["push", "origin", tag])?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAGS: [&str; 6] = [
+        "v1.2.2-rc.1",
+        "v1.2.2",
+        "v1.2.3-rc.1",
+        "v1.2.3-rc.2",
+        "nightly",
+        "v1.3.0-rc.1",
+    ];
+
+    fn previous(version: &str) -> Option<String> {
+        previous_release(&semver::Version::parse(version).unwrap(), TAGS)
+    }
+
+    #[test]
+    fn final_release_follows_previous_final() {
+        assert_eq!(previous("1.2.3").as_deref(), Some("v1.2.2"));
+        assert_eq!(previous("1.3.0").as_deref(), Some("v1.2.2"));
+        assert_eq!(previous("1.2.2"), None);
+    }
+
+    #[test]
+    fn prerelease_follows_previous_tag() {
+        assert_eq!(previous("1.2.3-rc.3").as_deref(), Some("v1.2.3-rc.2"));
+        assert_eq!(previous("1.2.3-rc.1").as_deref(), Some("v1.2.2"));
+    }
+
+    #[test]
+    fn clone_output_tail_is_capped() {
+        let mut tail = Vec::new();
+        let mut seen = 0;
+        for index in 0..=CLONE_ERROR_TAIL_LINES {
+            let mut line = format!("line {index}").into_bytes();
+            finish_clone_line(&mut line, &mut tail, &mut |_| seen += 1);
+        }
+        let mut partial = b"fatal: unfinished".to_vec();
+        finish_clone_line(&mut partial, &mut tail, &mut |_| seen += 1);
+
+        assert_eq!(seen, CLONE_ERROR_TAIL_LINES + 2);
+        assert_eq!(tail.len(), CLONE_ERROR_TAIL_LINES);
+        assert_eq!(tail.first().unwrap(), "line 2");
+        assert_eq!(tail.last().unwrap(), "fatal: unfinished");
+    }
+}
@@ -0,0 +1,471 @@
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Result, bail};
+
+use crate::git::{Commit, run_git, run_git_status};
+
+/// Read-only git queries, implemented either by spawning the git CLI or natively.
+///
+/// Anything that talks to a remote or writes refs (fetch, tag, push) stays on the CLI.
+pub trait GitBackend: Send + Sync {
+    /// Resolves `rev` to the commit it ultimately points at.
+    ///
+    /// Revs that don't exist, are malformed or ambiguous, or don't peel to a commit are all
+    /// `Ok(None)`, the way `git rev-parse --verify --quiet` treats them.
+    fn resolve_commit(&self, dir: &Path, rev: &str) -> Result<Option<String>>;
+
+    /// Uncommitted changes in `git status --porcelain` form (empty when clean).
+    fn status(&self, dir: &Path) -> Result<Vec<String>>;
+
+    /// Short name of the checked-out branch, or `None` when HEAD is detached.
+    fn current_branch(&self, dir: &Path) -> Result<Option<String>>;
+
+    /// Commits reachable only from `local` and only from `upstream`.
+    fn ahead_behind(&self, dir: &Path, local: &str, upstream: &str) -> Result<(usize, usize)>;
+
+    /// Whether `ancestor` is reachable from `descendant` (a commit is its own ancestor).
+    fn is_ancestor(&self, dir: &Path, ancestor: &str, descendant: &str) -> Result<bool>;
+
+    /// Branches on origin (as of the last fetch) whose history contains `commit`.
+    fn remote_branches_containing(&self, dir: &Path, commit: &str) -> Result<Vec<String>>;
+
+    /// First line of `commit`'s message.
+    fn commit_subject(&self, dir: &Path, commit: &str) -> Result<String>;
+
+    /// Contents of `path` as of `commit`, without touching the checkout.
+    fn read_file_at(&self, dir: &Path, commit: &str, path: &str) -> Result<String>;
+
+    /// Local tag names matching the glob `pattern`, limited to those reachable from
+    /// `merged_into` when given.
+    fn tags(&self, dir: &Path, pattern: &str, merged_into: Option<&str>) -> Result<Vec<String>>;
+
+    /// Non-merge commits in `from..to` (or all of `to`'s history without `from`), newest first.
+    fn commits_between(&self, dir: &Path, from: Option<&str>, to: &str) -> Result<Vec<Commit>>;
+
+    /// URL of the remote `name`.
+    fn remote_url(&self, dir: &Path, name: &str) -> Result<String>;
+
+    /// Commit a local tag points at, or `None` if the tag doesn't exist.
+    fn local_tag_commit(&self, dir: &Path, tag: &str) -> Result<Option<String>> {
+        self.resolve_commit(dir, &format!("refs/tags/{tag}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BackendKind {
+    /// Spawn the `git` executable.
+    #[default]
+    Cli,
+    /// Use libgit2 in-process (requires the `native-git` feature).
+    Native,
+}
+
+static BACKEND: OnceLock<Arc<dyn GitBackend>> = OnceLock::new();
+
+/// Selects the process-wide backend. Must be called before the first `Repo` is created.
+pub fn init(kind: BackendKind) -> Result<()> {
+    let backend: Arc<dyn GitBackend> = match kind {
+        BackendKind::Cli => Arc::new(CliBackend),
+        #[cfg(feature = "native-git")]
+        BackendKind::Native => Arc::new(native::NativeBackend),
+        #[cfg(not(feature = "native-git"))]
+        BackendKind::Native => {
+            bail!(
+                "this orchestrator was built without the native-git feature; use --git-backend cli"
+            )
+        }
+    };
+
+    if BACKEND.set(backend).is_err() {
+        bail!("git backend already initialized");
+    }
+    Ok(())
+}
+
+pub fn backend() -> Arc<dyn GitBackend> {
+    BACKEND.get_or_init(|| Arc::new(CliBackend)).clone()
+}
+
+pub struct CliBackend;
+
+impl GitBackend for CliBackend {
+    fn resolve_commit(&self, dir: &Path, rev: &str) -> Result<Option<String>> {
+        let spec = format!("{rev}^{{commit}}");
+        let (code, stdout, _stderr) =
+            run_git_status(dir, &["rev-parse", "--verify", "--quiet", &spec])?;
+        if code != 0 || stdout.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(stdout))
+    }
+
+    fn status(&self, dir: &Path) -> Result<Vec<String>> {
+        let status = run_git(dir, &["status", "--porcelain"])?;
+        Ok(status.lines().map(str::to_string).collect())
+    }
+
+    fn current_branch(&self, dir: &Path) -> Result<Option<String>> {
+        let (code, branch, _) =
+            run_git_status(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"])?;
+        if code != 0 || branch.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(branch))
+    }
+
+    fn ahead_behind(&self, dir: &Path, local: &str, upstream: &str) -> Result<(usize, usize)> {
+        let counts = run_git(
+            dir,
+            &[
+                "rev-list",
+                "--left-right",
+                "--count",
+                &format!("{local}...{upstream}"),
+            ],
+        )?;
+        let mut parts = counts.split_whitespace().map(str::parse::<usize>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(ahead)), Some(Ok(behind))) => Ok((ahead, behind)),
+            _ => bail!("unexpected rev-list output in {}: {counts}", dir.display()),
+        }
+    }
+
+    fn is_ancestor(&self, dir: &Path, ancestor: &str, descendant: &str) -> Result<bool> {
+        let (code, _stdout, stderr) =
+            run_git_status(dir, &["merge-base", "--is-ancestor", ancestor, descendant])?;
+        match code {
+            0 => Ok(true),
+            1 => Ok(false),
+            _ => bail!(
+                "{} failed to compare {ancestor} and {descendant} (exit={code}): {stderr}",
+                dir.display()
+            ),
+        }
+    }
+
+    fn remote_branches_containing(&self, dir: &Path, commit: &str) -> Result<Vec<String>> {
+        let branches = run_git(
+            dir,
+            &[
+                "branch",
+                "--remotes",
+                "--contains",
+                commit,
+                "--format=%(refname:short)",
+            ],
+        )?;
+        Ok(branches
+            .lines()
+            .filter_map(|branch| branch.strip_prefix("origin/"))
+            .filter(|branch| *branch != "HEAD")
+            .map(str::to_string)
+            .collect())
+    }
+
+    fn commit_subject(&self, dir: &Path, commit: &str) -> Result<String> {
+        run_git(dir, &["log", "-1", "--format=%s", commit])
+    }
+
+    fn read_file_at(&self, dir: &Path, commit: &str, path: &str) -> Result<String> {
+        run_git(dir, &["show", &format!("{commit}:{path}")])
+    }
+
+    fn tags(&self, dir: &Path, pattern: &str, merged_into: Option<&str>) -> Result<Vec<String>> {
+        let mut args = vec!["tag", "--list", pattern];
+        if let Some(commit) = merged_into {
+            args.extend(["--merged", commit]);
+        }
+        Ok(run_git(dir, &args)?.lines().map(str::to_string).collect())
+    }
+
+    fn commits_between(&self, dir: &Path, from: Option<&str>, to: &str) -> Result<Vec<Commit>> {
+        let range = match from {
+            Some(from) => format!("{from}..{to}"),
+            None => to.to_string(),
+        };
+        let log = run_git(dir, &["log", "--no-merges", "--format=%H%x09%s", &range])?;
+        Ok(log
+            .lines()
+            .filter_map(|line| {
+                let (sha, subject) = line.split_once('\t')?;
+                Some(Commit {
+                    sha: sha.to_string(),
+                    subject: subject.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn remote_url(&self, dir: &Path, name: &str) -> Result<String> {
+        run_git(dir, &["remote", "get-url", name])
+    }
+}
+
+#[cfg(feature = "native-git")]
+mod native {
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use git2::{BranchType, ErrorCode, Oid, Repository, Sort, Status, StatusOptions};
+
+    use super::GitBackend;
+    use crate::git::Commit;
+
+    pub struct NativeBackend;
+
+    fn open(dir: &Path) -> Result<Repository> {
+        Repository::open(dir).with_context(|| format!("failed to open git repo {}", dir.display()))
+    }
+
+    impl GitBackend for NativeBackend {
+        fn resolve_commit(&self, dir: &Path, rev: &str) -> Result<Option<String>> {
+            let repo = open(dir)?;
+            let object = match repo.revparse_single(rev) {
+                Ok(object) => object,
+                Err(err)
+                    if matches!(
+                        err.code(),
+                        ErrorCode::NotFound | ErrorCode::InvalidSpec | ErrorCode::Ambiguous
+                    ) =>
+                {
+                    return Ok(None);
+                }
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to resolve {rev} in {}", dir.display()));
+                }
+            };
+            Ok(object
+                .peel_to_commit()
+                .ok()
+                .map(|commit| commit.id().to_string()))
+        }
+
+        fn status(&self, dir: &Path) -> Result<Vec<String>> {
+            let repo = open(dir)?;
+            let mut options = StatusOptions::new();
+            options
+                .include_untracked(true)
+                .recurse_untracked_dirs(false)
+                .include_ignored(false);
+            let statuses = repo
+                .statuses(Some(&mut options))
+                .with_context(|| format!("failed to read status of {}", dir.display()))?;
+
+            Ok(statuses
+                .iter()
+                .map(|entry| {
+                    let path = entry.path().unwrap_or("<non-utf8 path>");
+                    format!("{} {path}", porcelain_code(entry.status()))
+                })
+                .collect())
+        }
+
+        fn current_branch(&self, dir: &Path) -> Result<Option<String>> {
+            let repo = open(dir)?;
+            if repo
+                .head_detached()
+                .with_context(|| format!("failed to read HEAD of {}", dir.display()))?
+            {
+                return Ok(None);
+            }
+            let head = match repo.head() {
+                Ok(head) => head,
+                // Unborn branch: HEAD names a branch with no commits yet.
+                Err(err) if err.code() == ErrorCode::UnbornBranch => return Ok(None),
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to read HEAD of {}", dir.display()));
+                }
+            };
+            Ok(head.shorthand().map(str::to_string))
+        }
+
+        fn ahead_behind(&self, dir: &Path, local: &str, upstream: &str) -> Result<(usize, usize)> {
+            let repo = open(dir)?;
+            let resolve = |rev: &str| {
+                repo.revparse_single(rev)
+                    .and_then(|object| object.peel_to_commit())
+                    .map(|commit| commit.id())
+                    .with_context(|| format!("failed to resolve {rev} in {}", dir.display()))
+            };
+            let (local, upstream) = (resolve(local)?, resolve(upstream)?);
+            repo.graph_ahead_behind(local, upstream)
+                .with_context(|| format!("failed to count commits in {}", dir.display()))
+        }
+
+        fn is_ancestor(&self, dir: &Path, ancestor: &str, descendant: &str) -> Result<bool> {
+            let repo = open(dir)?;
+            let (ancestor, descendant) = (
+                commit(&repo, ancestor)?.id(),
+                commit(&repo, descendant)?.id(),
+            );
+            Ok(ancestor == descendant || contains(&repo, descendant, ancestor)?)
+        }
+
+        fn remote_branches_containing(&self, dir: &Path, commit_rev: &str) -> Result<Vec<String>> {
+            let repo = open(dir)?;
+            let target = commit(&repo, commit_rev)?.id();
+            let branches = repo
+                .branches(Some(BranchType::Remote))
+                .with_context(|| format!("failed to list remote branches in {}", dir.display()))?;
+
+            let mut names = Vec::new();
+            for branch in branches {
+                let (branch, _) = branch.with_context(|| {
+                    format!("failed to read remote branches in {}", dir.display())
+                })?;
+                // Symbolic refs such as origin/HEAD have no direct target.
+                let (Some(name), Some(tip)) = (branch.name()?, branch.get().target()) else {
+                    continue;
+                };
+                let Some(name) = name.strip_prefix("origin/") else {
+                    continue;
+                };
+                if name != "HEAD" && (tip == target || contains(&repo, tip, target)?) {
+                    names.push(name.to_string());
+                }
+            }
+            names.sort();
+            Ok(names)
+        }
+
+        fn commit_subject(&self, dir: &Path, rev: &str) -> Result<String> {
+            let repo = open(dir)?;
+            let commit = commit(&repo, rev)?;
+            Ok(commit.summary().unwrap_or_default().to_string())
+        }
+
+        fn read_file_at(&self, dir: &Path, rev: &str, path: &str) -> Result<String> {
+            let repo = open(dir)?;
+            let tree = commit(&repo, rev)?.tree().with_context(|| {
+                format!("failed to read the tree of {rev} in {}", dir.display())
+            })?;
+            let blob = tree
+                .get_path(Path::new(path))
+                .and_then(|entry| entry.to_object(&repo))
+                .and_then(|object| object.peel_to_blob())
+                .with_context(|| format!("{path} not found at {rev} in {}", dir.display()))?;
+            String::from_utf8(blob.content().to_vec())
+                .with_context(|| format!("{path} at {rev} in {} is not UTF-8", dir.display()))
+        }
+
+        fn tags(
+            &self,
+            dir: &Path,
+            pattern: &str,
+            merged_into: Option<&str>,
+        ) -> Result<Vec<String>> {
+            let repo = open(dir)?;
+            let names = repo
+                .tag_names(Some(pattern))
+                .with_context(|| format!("failed to list tags in {}", dir.display()))?;
+            let merged_into = merged_into.map(|rev| commit(&repo, rev)).transpose()?;
+
+            let mut tags = Vec::new();
+            for name in names.iter().flatten() {
+                if let Some(head) = &merged_into {
+                    // Tags that don't point at a commit can't be merged into one.
+                    let Ok(tagged) = commit(&repo, &format!("refs/tags/{name}")) else {
+                        continue;
+                    };
+                    if tagged.id() != head.id() && !contains(&repo, head.id(), tagged.id())? {
+                        continue;
+                    }
+                }
+                tags.push(name.to_string());
+            }
+            Ok(tags)
+        }
+
+        fn commits_between(&self, dir: &Path, from: Option<&str>, to: &str) -> Result<Vec<Commit>> {
+            let repo = open(dir)?;
+            let context = || format!("failed to walk history in {}", dir.display());
+            let mut walk = repo.revwalk().with_context(context)?;
+            walk.set_sorting(Sort::TIME).with_context(context)?;
+            walk.push(commit(&repo, to)?.id()).with_context(context)?;
+            if let Some(from) = from {
+                walk.hide(commit(&repo, from)?.id()).with_context(context)?;
+            }
+
+            let mut commits = Vec::new();
+            for id in walk {
+                let commit = repo
+                    .find_commit(id.with_context(context)?)
+                    .with_context(context)?;
+                if commit.parent_count() > 1 {
+                    continue;
+                }
+                commits.push(Commit {
+                    sha: commit.id().to_string(),
+                    subject: commit.summary().unwrap_or_default().to_string(),
+                });
+            }
+            Ok(commits)
+        }
+
+        fn remote_url(&self, dir: &Path, name: &str) -> Result<String> {
+            let repo = open(dir)?;
+            let remote = repo
+                .find_remote(name)
+                .with_context(|| format!("{} has no remote {name}", dir.display()))?;
+            remote
+                .url()
+                .map(str::to_string)
+                .with_context(|| format!("remote {name} in {} has a non-UTF-8 URL", dir.display()))
+        }
+    }
+
+    fn commit<'r>(repo: &'r Repository, rev: &str) -> Result<git2::Commit<'r>> {
+        repo.revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .with_context(|| {
+                format!(
+                    "failed to resolve {rev} in {}",
+                    repo.workdir().unwrap_or(repo.path()).display()
+                )
+            })
+    }
+
+    /// Whether `ancestor` is in `descendant`'s history (strictly; equal commits are `false`).
+    fn contains(repo: &Repository, descendant: Oid, ancestor: Oid) -> Result<bool> {
+        repo.graph_descendant_of(descendant, ancestor)
+            .context("failed to compare commits")
+    }
+
+    fn porcelain_code(status: Status) -> String {
+        if status.is_wt_new() {
+            return "??".to_string();
+        }
+        if status.is_conflicted() {
+            return "UU".to_string();
+        }
+
+        let index = if status.is_index_new() {
+            'A'
+        } else if status.is_index_modified() {
+            'M'
+        } else if status.is_index_deleted() {
+            'D'
+        } else if status.is_index_renamed() {
+            'R'
+        } else if status.is_index_typechange() {
+            'T'
+        } else {
+            ' '
+        };
+        let worktree = if status.is_wt_modified() {
+            'M'
+        } else if status.is_wt_deleted() {
+            'D'
+        } else if status.is_wt_renamed() {
+            'R'
+        } else if status.is_wt_typechange() {
+            'T'
+        } else {
+            ' '
+        };
+        format!("{index}{worktree}")
+    }
+}
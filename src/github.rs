@@ -2,15 +2,35 @@ use anyhow::{Context, Result, anyhow, bail};
 use reqwest::blocking::{Client, Response};
 use reqwest::{StatusCode, Url};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
 use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+use crate::arch;
+use crate::cancel::{CancelReader, CancelToken, Cancelled};
 use crate::reporter::Reporter;
 
 pub const PRIMARY_GITHUB_TOKEN_ENV: &str = "GITHUB_TRUTHDB_TOKEN";
 pub const FALLBACK_GITHUB_TOKEN_ENV: &str = "GH_TOKEN";
 pub const LEGACY_GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
 
+/// Workflow that runs CI on each repo's branches.
+pub const CI_WORKFLOW_FILE: &str = "ci.yml";
+
+/// Workflow triggered by pushing a release tag; it builds and uploads the release assets.
+pub const RELEASE_WORKFLOW_FILE: &str = "release.yml";
+
+/// How long a pushed tag may go without a workflow run before we fall back to asset polling.
+const WORKFLOW_RUN_DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+
+/// Upper bound for a single asset download; the ISO can be large.
+const ASSET_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Log lines of a failed job included in the error.
+const FAILED_JOB_LOG_LINES: usize = 40;
+
 pub fn github_token() -> String {
     std::env::var(PRIMARY_GITHUB_TOKEN_ENV)
         .or_else(|_| std::env::var(FALLBACK_GITHUB_TOKEN_ENV))
@@ -22,10 +42,14 @@ pub fn github_token() -> String {
 pub struct ReleaseAsset {
     pub name: String,
     pub size: u64,
+    #[serde(default)]
+    pub browser_download_url: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Release {
+    #[serde(default)]
+    pub id: u64,
     pub assets: Vec<ReleaseAsset>,
 }
 
@@ -41,13 +65,44 @@ pub struct WorkflowRunsResponse {
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct WorkflowRun {
+    #[serde(default)]
+    pub id: u64,
     pub status: String,
     pub conclusion: Option<String>,
+    #[serde(default)]
+    pub head_sha: String,
+    #[serde(default)]
+    pub html_url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowJobsResponse {
+    pub jobs: Vec<WorkflowJob>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowJob {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub steps: Vec<WorkflowStep>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkflowStep {
+    pub name: String,
+    pub conclusion: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct LatestRelease {
     pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -60,6 +115,7 @@ pub struct GitHub {
     owner: String,
     token: String,
     client: Client,
+    cancel: CancelToken,
 }
 
 impl GitHub {
@@ -72,9 +128,16 @@ impl GitHub {
             owner: owner.into(),
             token: token.into(),
             client,
+            cancel: CancelToken::default(),
         })
     }
 
+    /// Makes polling waits and downloads stop once `cancel` is cancelled.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     fn get(&self, url: String) -> reqwest::blocking::RequestBuilder {
         let req = self.client.get(url);
         if self.token.trim().is_empty() {
@@ -171,22 +234,14 @@ impl GitHub {
         Ok(info.default_branch)
     }
 
-    pub fn get_latest_workflow_run(
+    /// GETs `url` and parses the JSON body; `None` on 404.
+    fn get_json<T: DeserializeOwned>(
         &self,
         repo: &str,
-        workflow_file: &str,
-        branch: &str,
-    ) -> Result<Option<WorkflowRun>> {
-        let mut url = Url::parse(&format!(
-            "https://api.github.com/repos/{}/{repo}/actions/workflows/{}/runs",
-            self.owner, workflow_file
-        ))
-        .context("failed to build GitHub workflow runs URL")?;
-        url.query_pairs_mut()
-            .append_pair("per_page", "1")
-            .append_pair("branch", branch);
-
-        let resp = self.send_get(url.as_str())?;
+        url: &str,
+        what: &str,
+    ) -> Result<Option<T>> {
+        let resp = self.send_get(url)?;
 
         if resp.status() == StatusCode::NOT_FOUND {
             return Ok(None);
@@ -210,13 +265,121 @@ impl GitHub {
             return Err(anyhow!("GitHub API error ({}): {}", status, body));
         }
 
-        let data = resp
-            .json::<WorkflowRunsResponse>()
-            .context("failed to parse GitHub workflow runs JSON")?;
-        Ok(data.workflow_runs.into_iter().next())
+        resp.json::<T>()
+            .map(Some)
+            .with_context(|| format!("failed to parse GitHub {what} JSON"))
+    }
+
+    fn list_workflow_runs(
+        &self,
+        repo: &str,
+        workflow_file: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<WorkflowRun>> {
+        let mut url = Url::parse(&format!(
+            "https://api.github.com/repos/{}/{repo}/actions/workflows/{}/runs",
+            self.owner, workflow_file
+        ))
+        .context("failed to build GitHub workflow runs URL")?;
+        url.query_pairs_mut().extend_pairs(query);
+
+        Ok(self
+            .get_json::<WorkflowRunsResponse>(repo, url.as_str(), "workflow runs")?
+            .map(|data| data.workflow_runs)
+            .unwrap_or_default())
+    }
+
+    pub fn get_latest_workflow_run(
+        &self,
+        repo: &str,
+        workflow_file: &str,
+        branch: &str,
+    ) -> Result<Option<WorkflowRun>> {
+        Ok(self
+            .list_workflow_runs(
+                repo,
+                workflow_file,
+                &[("per_page", "1"), ("branch", branch)],
+            )?
+            .into_iter()
+            .next())
+    }
+
+    /// Most recent run of `workflow_file` for commit `head_sha`, on any branch.
+    pub fn find_workflow_run_for_commit(
+        &self,
+        repo: &str,
+        workflow_file: &str,
+        head_sha: &str,
+    ) -> Result<Option<WorkflowRun>> {
+        Ok(self
+            .list_workflow_runs(
+                repo,
+                workflow_file,
+                &[("per_page", "1"), ("head_sha", head_sha)],
+            )?
+            .into_iter()
+            .next())
+    }
+
+    /// The push-triggered run of `workflow_file` for tag (or branch) `git_ref` at `head_sha`.
+    pub fn find_workflow_run_for_ref(
+        &self,
+        repo: &str,
+        workflow_file: &str,
+        git_ref: &str,
+        head_sha: &str,
+    ) -> Result<Option<WorkflowRun>> {
+        Ok(self
+            .list_workflow_runs(
+                repo,
+                workflow_file,
+                &[("per_page", "10"), ("event", "push"), ("branch", git_ref)],
+            )?
+            .into_iter()
+            .find(|run| run.head_sha == head_sha))
+    }
+
+    pub fn get_workflow_run(&self, repo: &str, run_id: u64) -> Result<WorkflowRun> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{repo}/actions/runs/{run_id}",
+            self.owner
+        );
+        self.get_json(repo, &url, "workflow run")?
+            .with_context(|| format!("{}/{repo} has no workflow run {run_id}", self.owner))
+    }
+
+    pub fn list_workflow_jobs(&self, repo: &str, run_id: u64) -> Result<Vec<WorkflowJob>> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{repo}/actions/runs/{run_id}/jobs?per_page=100",
+            self.owner
+        );
+        Ok(self
+            .get_json::<WorkflowJobsResponse>(repo, &url, "workflow jobs")?
+            .map(|data| data.jobs)
+            .unwrap_or_default())
+    }
+
+    /// Plain-text log of a finished job.
+    pub fn get_job_log(&self, repo: &str, job_id: u64) -> Result<String> {
+        let url = format!(
+            "https://api.github.com/repos/{}/{repo}/actions/jobs/{job_id}/logs",
+            self.owner
+        );
+        let resp = self.send_get(&url)?;
+        if !resp.status().is_success() {
+            bail!("GitHub API error ({}) fetching job log", resp.status());
+        }
+        resp.text().context("failed to read job log")
     }
 
     pub fn get_latest_release_tag(&self, repo: &str) -> Result<Option<String>> {
+        Ok(self
+            .get_latest_release(repo)?
+            .map(|release| release.tag_name))
+    }
+
+    pub fn get_latest_release(&self, repo: &str) -> Result<Option<LatestRelease>> {
         let url = format!(
             "https://api.github.com/repos/{}/{repo}/releases/latest",
             self.owner
@@ -249,7 +412,128 @@ impl GitHub {
         let release = resp
             .json::<LatestRelease>()
             .context("failed to parse GitHub latest release JSON")?;
-        Ok(Some(release.tag_name))
+        Ok(Some(release))
+    }
+
+    /// Downloads `asset` into memory; for small assets such as checksums and the launcher.
+    pub fn download_asset(&self, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+        let (mut resp, _) = self.start_asset_download(asset, 0)?;
+        let mut bytes = Vec::new();
+        self.copy_download(
+            asset,
+            &mut resp,
+            &mut bytes,
+            format!("download of {} interrupted", asset.name),
+        )?;
+        if asset.size > 0 && bytes.len() as u64 != asset.size {
+            bail!(
+                "downloaded {} is {} bytes, expected {}",
+                asset.name,
+                bytes.len(),
+                asset.size
+            );
+        }
+        Ok(bytes)
+    }
+
+    /// Downloads `asset` to `dest`, resuming a `<dest>.part` left by an interrupted attempt.
+    pub fn download_asset_to(&self, asset: &ReleaseAsset, dest: &Path) -> Result<()> {
+        let part = part_path(dest);
+        let mut offset = fs::metadata(&part).map(|meta| meta.len()).unwrap_or(0);
+        if offset > asset.size {
+            fs::remove_file(&part)
+                .with_context(|| format!("failed to remove {}", part.display()))?;
+            offset = 0;
+        }
+
+        if offset < asset.size || asset.size == 0 {
+            let (mut resp, append) = self.start_asset_download(asset, offset)?;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(append)
+                .truncate(!append)
+                .open(&part)
+                .with_context(|| format!("failed to open {}", part.display()))?;
+            self.copy_download(
+                asset,
+                &mut resp,
+                &mut file,
+                format!(
+                    "download of {} interrupted; re-run to resume from {}",
+                    asset.name,
+                    part.display()
+                ),
+            )?;
+        }
+
+        let size = fs::metadata(&part)
+            .with_context(|| format!("failed to stat {}", part.display()))?
+            .len();
+        if asset.size > 0 && size != asset.size {
+            bail!(
+                "downloaded {} is {size} bytes, expected {}; re-run to resume",
+                asset.name,
+                asset.size
+            );
+        }
+
+        fs::rename(&part, dest)
+            .with_context(|| format!("failed to move download into {}", dest.display()))
+    }
+
+    /// Requests `asset` from byte `offset`, returning the response and whether it continues
+    /// from `offset` (rather than starting over).
+    ///
+    /// Like [`Self::send_get`], a rejected token is retried anonymously, since release
+    /// assets of public repos don't need one.
+    fn start_asset_download(&self, asset: &ReleaseAsset, offset: u64) -> Result<(Response, bool)> {
+        if asset.browser_download_url.is_empty() {
+            bail!("release asset {} has no download URL", asset.name);
+        }
+
+        let send = |req: reqwest::blocking::RequestBuilder| {
+            let mut req = req.timeout(ASSET_DOWNLOAD_TIMEOUT);
+            if offset > 0 {
+                req = req.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+            }
+            req.send()
+                .with_context(|| format!("failed to download {}", asset.name))
+        };
+        let mut resp = send(self.get(asset.browser_download_url.clone()))?;
+        if !self.token.trim().is_empty()
+            && matches!(
+                resp.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            )
+        {
+            resp = send(self.client.get(&asset.browser_download_url))?;
+        }
+
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => Ok((resp, true)),
+            StatusCode::OK => Ok((resp, false)),
+            status => bail!("failed to download {} (status {})", asset.name, status),
+        }
+    }
+
+    /// Copies a download into `out`, stopping promptly once cancelled.
+    fn copy_download(
+        &self,
+        asset: &ReleaseAsset,
+        resp: &mut Response,
+        out: &mut impl std::io::Write,
+        interrupted: String,
+    ) -> Result<()> {
+        let copied = std::io::copy(&mut CancelReader::new(resp, self.cancel.clone()), out);
+        if self.cancel.is_cancelled() {
+            return Err(Cancelled {
+                stage: format!("downloading {}", asset.name),
+            }
+            .into());
+        }
+        copied.context(interrupted)?;
+        Ok(())
     }
 
     pub fn compare_ahead_by(&self, repo: &str, base: &str, head: &str) -> Result<u32> {
@@ -288,6 +572,186 @@ impl GitHub {
         Ok(cmp.ahead_by)
     }
 
+    /// Replaces the body of the release for `tag`. Requires a token with write access.
+    pub fn update_release_body(&self, repo: &str, tag: &str, body: &str) -> Result<()> {
+        let release = self
+            .get_release_by_tag(repo, tag)?
+            .with_context(|| format!("{}/{repo} has no release for {tag}", self.owner))?;
+
+        if self.token.trim().is_empty() {
+            bail!(
+                "publishing release notes requires {}, {}, or {} with write access to {}/{}",
+                PRIMARY_GITHUB_TOKEN_ENV,
+                FALLBACK_GITHUB_TOKEN_ENV,
+                LEGACY_GITHUB_TOKEN_ENV,
+                self.owner,
+                repo
+            );
+        }
+
+        let url = format!(
+            "https://api.github.com/repos/{}/{repo}/releases/{}",
+            self.owner, release.id
+        );
+        let resp = self
+            .client
+            .patch(&url)
+            .bearer_auth(&self.token)
+            .json(&serde_json::json!({ "body": body }))
+            .send()
+            .context("GitHub API request failed")?;
+
+        if resp.status() == StatusCode::UNAUTHORIZED || resp.status() == StatusCode::FORBIDDEN {
+            bail!(
+                "GitHub API auth failed (status {}). The token needs Contents: Read and write on {}/{}.",
+                resp.status(),
+                self.owner,
+                repo
+            );
+        }
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().unwrap_or_default();
+            return Err(anyhow!("GitHub API error ({}): {}", status, body));
+        }
+
+        Ok(())
+    }
+
+    /// Follows the `release.yml` run triggered by pushing `tag` until it finishes.
+    ///
+    /// Returns `Ok(false)` if no run shows up for the tag, so the caller can fall back to
+    /// polling assets. A failed run is an error naming the failing job with a log excerpt.
+    pub fn wait_for_release_workflow(
+        &self,
+        repo: &str,
+        tag: &str,
+        head_sha: &str,
+        poll_interval: Duration,
+        timeout: Duration,
+        reporter: &dyn Reporter,
+    ) -> Result<bool> {
+        let workflow_file = RELEASE_WORKFLOW_FILE;
+        let started = Instant::now();
+        let deadline = started + timeout;
+
+        let run_id = loop {
+            if let Some(run) = self.find_workflow_run_for_ref(repo, workflow_file, tag, head_sha)? {
+                break run.id;
+            }
+            if started.elapsed() > WORKFLOW_RUN_DISCOVERY_TIMEOUT.min(timeout) {
+                reporter.update(format!(
+                    "[{repo}] no {workflow_file} run found for {tag}; falling back to asset polling"
+                ));
+                return Ok(false);
+            }
+            reporter.update(format!(
+                "[{repo}] waiting for {workflow_file} run for {tag}…"
+            ));
+            self.cancel
+                .sleep(poll_interval, &format!("waiting for {workflow_file}"))?;
+        };
+
+        let mut last_summary = String::new();
+        loop {
+            if Instant::now() > deadline {
+                bail!(
+                    "Timed out waiting for {}/{repo} {workflow_file} run {run_id} for {tag}",
+                    self.owner
+                );
+            }
+
+            let run = self.get_workflow_run(repo, run_id)?;
+            let jobs = self.list_workflow_jobs(repo, run_id)?;
+
+            let summary = jobs
+                .iter()
+                .map(|job| {
+                    format!(
+                        "{}: {}",
+                        job.name,
+                        job.conclusion.as_deref().unwrap_or(&job.status)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            if summary != last_summary {
+                reporter.update(format!(
+                    "[{repo}] {workflow_file} {}: {summary}",
+                    run.status
+                ));
+                last_summary = summary;
+            }
+
+            // A failed job fails the release even while other matrix jobs are still running.
+            if jobs
+                .iter()
+                .any(|job| job.conclusion.as_deref() == Some("failure"))
+            {
+                bail!(self.describe_failed_run(repo, workflow_file, &run, &jobs));
+            }
+
+            if run.status == "completed" {
+                if run.conclusion.as_deref() == Some("success") {
+                    reporter.update(format!("[{repo}] {workflow_file} succeeded for {tag}"));
+                    return Ok(true);
+                }
+                bail!(self.describe_failed_run(repo, workflow_file, &run, &jobs));
+            }
+
+            self.cancel
+                .sleep(poll_interval, &format!("waiting for {workflow_file}"))?;
+        }
+    }
+
+    fn describe_failed_run(
+        &self,
+        repo: &str,
+        workflow_file: &str,
+        run: &WorkflowRun,
+        jobs: &[WorkflowJob],
+    ) -> String {
+        let conclusion = run.conclusion.as_deref().unwrap_or("failed");
+        let failed = jobs
+            .iter()
+            .find(|job| job.conclusion.as_deref() == Some("failure"));
+        let Some(job) = failed.or_else(|| {
+            jobs.iter().find(|job| {
+                !matches!(
+                    job.conclusion.as_deref(),
+                    Some("success" | "skipped") | None
+                )
+            })
+        }) else {
+            return format!("{repo} {workflow_file} {conclusion} ({})", run.html_url);
+        };
+
+        let mut message = format!("{repo} {workflow_file} {conclusion} in job '{}'", job.name);
+        if let Some(step) = job
+            .steps
+            .iter()
+            .find(|step| step.conclusion.as_deref() == Some("failure"))
+        {
+            message.push_str(&format!(" at step '{}'", step.name));
+        }
+        message.push_str(&format!(" ({})", job.html_url));
+
+        match self.get_job_log(repo, job.id) {
+            Ok(log) => {
+                let lines: Vec<&str> = log.lines().map(strip_log_timestamp).collect();
+                let tail = &lines[lines.len().saturating_sub(FAILED_JOB_LOG_LINES)..];
+                message.push_str(&format!(
+                    "\n--- last {} log lines ---\n{}",
+                    tail.len(),
+                    tail.join("\n")
+                ));
+            }
+            Err(err) => message.push_str(&format!("\n(job log unavailable: {err})")),
+        }
+        message
+    }
+
     pub fn wait_for_release_assets(
         &self,
         repo: &str,
@@ -300,20 +764,29 @@ impl GitHub {
         let deadline = Instant::now() + timeout;
         let mut last_sizes: Option<BTreeMap<String, u64>> = None;
         let mut stable_count = 0u32;
+        let mut missing = expected_assets.to_vec();
+        let mut polled = false;
 
         loop {
-            if Instant::now() > deadline {
+            // Always poll once, so a caller passing what's left of a shared deadline still
+            // sees assets that are already there.
+            if polled && Instant::now() > deadline {
+                let detail = if missing.is_empty() {
+                    "sizes never stabilized".to_string()
+                } else {
+                    format!("still missing {}", arch::describe_missing(&missing))
+                };
                 bail!(
-                    "Timed out waiting for {}/{} {tag} assets: {:?}",
+                    "Timed out waiting for {}/{} {tag} assets ({detail})",
                     self.owner,
-                    repo,
-                    expected_assets
+                    repo
                 );
             }
 
+            polled = true;
             let Some(release) = self.get_release_by_tag(repo, tag)? else {
                 reporter.update(format!("[{repo}] release {tag} not found yet; waiting…"));
-                std::thread::sleep(poll_interval);
+                self.cancel.sleep(poll_interval, "waiting for assets")?;
                 continue;
             };
 
@@ -322,7 +795,7 @@ impl GitHub {
                 sizes.insert(asset.name.clone(), asset.size);
             }
 
-            let mut missing = Vec::new();
+            missing.clear();
             for expected in expected_assets {
                 match sizes.get(expected) {
                     Some(sz) if *sz > 0 => {}
@@ -332,11 +805,11 @@ impl GitHub {
 
             if !missing.is_empty() {
                 reporter.update(format!(
-                    "[{repo}] waiting for assets (missing {}): {:?}",
+                    "[{repo}] waiting for assets (missing {}): {}",
                     missing.len(),
-                    missing
+                    arch::describe_missing(&missing)
                 ));
-                std::thread::sleep(poll_interval);
+                self.cancel.sleep(poll_interval, "waiting for assets")?;
                 continue;
             }
 
@@ -354,7 +827,21 @@ impl GitHub {
             }
 
             reporter.update(format!("[{repo}] assets present; verifying stability…"));
-            std::thread::sleep(poll_interval);
+            self.cancel.sleep(poll_interval, "waiting for assets")?;
         }
     }
 }
+
+/// Drops the RFC 3339 timestamp GitHub prefixes to each job log line.
+fn strip_log_timestamp(line: &str) -> &str {
+    match line.split_once(' ') {
+        Some((stamp, rest)) if stamp.ends_with('Z') && stamp.contains('T') => rest,
+        _ => line,
+    }
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
@@ -0,0 +1,319 @@
+use crate::cancel::{CancelToken, Cancelled};
+use crate::release_definition::{ReleaseDefinition, SmokeTestDefinition};
+use crate::reporter::{DynReporter, Reporter};
+use anyhow::{Context, Result, bail};
+use crossbeam_channel::RecvTimeoutError;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Only x86_64 ISOs are booted; `release-iso --smoke-test` requires `--arch x86_64`.
+const DEFAULT_QEMU: &str = "qemu-system-x86_64";
+/// How long to wait for serial output before checking for cancellation again.
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+/// QEMU stderr lines kept for the error when it exits early.
+const STDERR_TAIL_LINES: usize = 5;
+
+/// Where distributions install the x86_64 OVMF firmware, most specific first.
+const OVMF_CANDIDATES: [&str; 6] = [
+    "/usr/share/OVMF/OVMF_CODE.fd",
+    "/usr/share/OVMF/OVMF_CODE_4M.fd",
+    "/usr/share/edk2/ovmf/OVMF_CODE.fd",
+    "/usr/share/edk2-ovmf/x64/OVMF_CODE.fd",
+    "/usr/share/ovmf/OVMF.fd",
+    "/usr/share/qemu/OVMF.fd",
+];
+
+#[derive(Clone, Debug)]
+pub struct IsoSmokeArgs {
+    pub iso: PathBuf,
+    pub release_definition: Option<PathBuf>,
+    pub qemu: Option<String>,
+    pub ovmf: Option<PathBuf>,
+    pub markers: Vec<String>,
+    pub timeout: Option<Duration>,
+    pub serial_log: Option<PathBuf>,
+    pub cancel: CancelToken,
+}
+
+/// How to boot and judge one ISO.
+#[derive(Clone, Debug)]
+pub struct SmokeOptions {
+    pub qemu: String,
+    pub ovmf: Option<PathBuf>,
+    pub success_markers: Vec<String>,
+    pub failure_markers: Vec<String>,
+    pub timeout: Duration,
+    pub memory_mb: u32,
+    pub serial_log: Option<PathBuf>,
+    pub cancel: CancelToken,
+}
+
+impl SmokeOptions {
+    pub fn from_definition(definition: &SmokeTestDefinition) -> Self {
+        Self {
+            qemu: DEFAULT_QEMU.to_string(),
+            ovmf: None,
+            success_markers: definition.success_markers.clone(),
+            failure_markers: definition.failure_markers.clone(),
+            timeout: Duration::from_secs(definition.timeout_secs),
+            memory_mb: definition.memory_mb,
+            serial_log: None,
+            cancel: CancelToken::default(),
+        }
+    }
+}
+
+pub fn run(args: IsoSmokeArgs, reporter: DynReporter) -> Result<()> {
+    let definition = ReleaseDefinition::load(args.release_definition.as_deref())?;
+    let mut options = SmokeOptions::from_definition(&definition.smoke_test);
+    if let Some(qemu) = args.qemu {
+        options.qemu = qemu;
+    }
+    options.ovmf = args.ovmf;
+    if !args.markers.is_empty() {
+        options.success_markers = args.markers;
+    }
+    if let Some(timeout) = args.timeout {
+        options.timeout = timeout;
+    }
+    options.serial_log = args.serial_log;
+    options.cancel = args.cancel;
+
+    smoke_test(&args.iso, &options, reporter.as_ref())?;
+    reporter.ok("OK".to_string());
+    Ok(())
+}
+
+/// Boots `iso` headless under QEMU (TCG, no GPU) and waits for a success marker on serial.
+pub fn smoke_test(iso: &Path, options: &SmokeOptions, reporter: &dyn Reporter) -> Result<()> {
+    if !iso.is_file() {
+        bail!("ISO not found: {}", iso.display());
+    }
+    if options.success_markers.is_empty() {
+        bail!("no success markers configured; set [smoke_test] success_markers or pass --marker");
+    }
+
+    let ovmf = match &options.ovmf {
+        Some(path) => path.clone(),
+        None => find_ovmf()?,
+    };
+    let serial_log = options
+        .serial_log
+        .clone()
+        .unwrap_or_else(|| default_serial_log(iso));
+
+    reporter.step(
+        "ISO smoke test".to_string(),
+        format!(
+            "iso={}\nfirmware={}\nserial log={}\ntimeout={}s",
+            iso.display(),
+            ovmf.display(),
+            serial_log.display(),
+            options.timeout.as_secs()
+        ),
+    );
+
+    // OVMF_VARS is writable, so boot from a scratch copy and leave the installed one alone.
+    let scratch = std::env::temp_dir().join(format!("orchestrator-smoke-{}", std::process::id()));
+    fs::create_dir_all(&scratch)
+        .with_context(|| format!("failed to create {}", scratch.display()))?;
+    let result = boot_and_watch(iso, &ovmf, &scratch, &serial_log, options, reporter);
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+fn boot_and_watch(
+    iso: &Path,
+    ovmf: &Path,
+    scratch: &Path,
+    serial_log: &Path,
+    options: &SmokeOptions,
+    reporter: &dyn Reporter,
+) -> Result<()> {
+    let mut command = Command::new(&options.qemu);
+    command
+        .args(["-machine", "q35", "-accel", "tcg", "-m"])
+        .arg(options.memory_mb.to_string())
+        .args(["-display", "none", "-vga", "none", "-monitor", "none"])
+        .args(["-serial", "stdio", "-no-reboot", "-boot", "d", "-cdrom"])
+        .arg(iso);
+    match ovmf_vars_for(ovmf) {
+        Some(vars) => {
+            let vars_copy = scratch.join("OVMF_VARS.fd");
+            fs::copy(&vars, &vars_copy)
+                .with_context(|| format!("failed to copy {}", vars.display()))?;
+            command
+                .arg("-drive")
+                .arg(format!(
+                    "if=pflash,format=raw,readonly=on,file={}",
+                    ovmf.display()
+                ))
+                .arg("-drive")
+                .arg(format!("if=pflash,format=raw,file={}", vars_copy.display()));
+        }
+        None => {
+            command.arg("-bios").arg(ovmf);
+        }
+    }
+
+    // QEMU's stderr is piped too so its warnings go through the reporter instead of
+    // writing over the TUI.
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to start {}; is QEMU installed?", options.qemu))?;
+    let mut log = File::create(serial_log)
+        .with_context(|| format!("failed to create {}", serial_log.display()))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("failed to capture QEMU serial output")?;
+    let stderr = child
+        .stderr
+        .take()
+        .context("failed to capture QEMU stderr")?;
+    let (tx, rx) = crossbeam_channel::unbounded();
+    forward_lines(stdout, tx.clone(), QemuOutput::Serial);
+    forward_lines(stderr, tx, QemuOutput::Stderr);
+    let mut stderr_tail = Vec::new();
+
+    let started = Instant::now();
+    let deadline = started + options.timeout;
+    let outcome = loop {
+        if options.cancel.is_cancelled() {
+            stop(&mut child);
+            return Err(Cancelled {
+                stage: "ISO smoke test".to_string(),
+            }
+            .into());
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let line = match rx.recv_timeout(remaining.min(CANCEL_CHECK_INTERVAL)) {
+            Ok(QemuOutput::Serial(line)) => line,
+            Ok(QemuOutput::Stderr(line)) => {
+                let line = line.trim_end();
+                if !line.is_empty() {
+                    reporter.progress("qemu".to_string(), line.to_string());
+                    if stderr_tail.len() == STDERR_TAIL_LINES {
+                        stderr_tail.remove(0);
+                    }
+                    stderr_tail.push(line.to_string());
+                }
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) if remaining > CANCEL_CHECK_INTERVAL => continue,
+            Err(RecvTimeoutError::Timeout) => {
+                break Err(format!(
+                    "no success marker within {}s",
+                    options.timeout.as_secs()
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) if stderr_tail.is_empty() => {
+                break Err("QEMU exited before a success marker appeared".to_string());
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                break Err(format!(
+                    "QEMU exited before a success marker appeared: {}",
+                    stderr_tail.join("; ")
+                ));
+            }
+        };
+
+        let line = line.trim_end_matches('\r');
+        if let Err(err) = writeln!(log, "{line}") {
+            break Err(format!("failed to write serial log: {err}"));
+        }
+        if !line.trim().is_empty() {
+            reporter.progress("serial".to_string(), line.to_string());
+        }
+
+        if let Some(marker) = options.failure_markers.iter().find(|m| line.contains(*m)) {
+            break Err(format!("failure marker '{marker}' on serial console"));
+        }
+        if let Some(marker) = options.success_markers.iter().find(|m| line.contains(*m)) {
+            break Ok(marker.clone());
+        }
+    };
+
+    stop(&mut child);
+
+    match outcome {
+        Ok(marker) => {
+            reporter.update(format!(
+                "saw '{marker}' after {}s",
+                started.elapsed().as_secs()
+            ));
+            Ok(())
+        }
+        Err(reason) => bail!(
+            "ISO smoke test failed: {reason}. Serial log: {}",
+            serial_log.display()
+        ),
+    }
+}
+
+/// A line QEMU wrote: the guest's serial console on stdout, or QEMU's own messages.
+enum QemuOutput {
+    Serial(String),
+    Stderr(String),
+}
+
+/// Sends each line of `stream` to `tx` from a background thread until either side closes.
+fn forward_lines(
+    stream: impl Read + Send + 'static,
+    tx: crossbeam_channel::Sender<QemuOutput>,
+    wrap: fn(String) -> QemuOutput,
+) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).split(b'\n') {
+            let Ok(line) = line else { break };
+            if tx
+                .send(wrap(String::from_utf8_lossy(&line).into_owned()))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+}
+
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn find_ovmf() -> Result<PathBuf> {
+    OVMF_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+        .with_context(|| {
+            format!(
+                "OVMF firmware not found (looked in {}); install ovmf/edk2-ovmf or pass --ovmf",
+                OVMF_CANDIDATES.join(", ")
+            )
+        })
+}
+
+/// The VARS image shipped next to a split `*_CODE*.fd` firmware, if any.
+fn ovmf_vars_for(code: &Path) -> Option<PathBuf> {
+    let name = code.file_name()?.to_str()?;
+    if !name.contains("_CODE") {
+        return None;
+    }
+    let vars = code.with_file_name(name.replacen("_CODE", "_VARS", 1));
+    vars.is_file().then_some(vars)
+}
+
+fn default_serial_log(iso: &Path) -> PathBuf {
+    let mut name = iso.file_name().unwrap_or_default().to_os_string();
+    name.push(".serial.log");
+    iso.with_file_name(name)
+}
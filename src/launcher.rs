@@ -0,0 +1,336 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::build_info;
+use crate::reporter::DynReporter;
+
+pub const BIN_DIR_NAME: &str = ".bin";
+pub const WRAPPER_NAME: &str = "orchestrator";
+pub const INSTALLED_BINARY_NAME: &str = ".orchestrator-bin";
+pub const DEFAULT_KEEP_VERSIONS: usize = 3;
+const VERSIONS_DIR_NAME: &str = "versions";
+const STATE_FILE_NAME: &str = "launcher.json";
+const LEGACY_STAMP: &str = "legacy";
+
+/// Launcher bookkeeping persisted as `.bin/launcher.json`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LauncherState {
+    /// Stamp of the binary currently installed as `.orchestrator-bin`.
+    #[serde(default)]
+    current: Option<String>,
+    /// Stamps of archived binaries under `.bin/versions/`, oldest first.
+    #[serde(default)]
+    history: Vec<String>,
+}
+
+/// `{version}-{git sha}` identifying the running orchestrator build.
+pub fn current_stamp() -> String {
+    let dirty = if build_info::git_dirty() {
+        "-dirty"
+    } else {
+        ""
+    };
+    format!("{}-{}{dirty}", build_info::VERSION, build_info::GIT_SHA)
+}
+
+/// Derives a stamp from `orchestrator --version` output such as `orchestrator 0.1.0 (abc123)`.
+pub fn stamp_from_version_output(output: &str) -> Option<String> {
+    let mut parts = output.split_whitespace().skip(1);
+    let version = parts.next()?;
+    let sha = parts
+        .next()
+        .map(|s| s.trim_matches(|c| c == '(' || c == ')'))
+        .unwrap_or("unknown");
+    Some(format!("{version}-{sha}"))
+}
+
+pub fn install_launcher(
+    workspace_root: &Path,
+    keep_versions: usize,
+    reporter: &DynReporter,
+) -> Result<bool> {
+    let bin_dir = workspace_root.join(BIN_DIR_NAME);
+    fs::create_dir_all(&bin_dir)
+        .with_context(|| format!("failed to create {}", bin_dir.display()))?;
+
+    let current_exe = env::current_exe().context("failed to resolve current executable")?;
+    let installed_binary = bin_dir.join(INSTALLED_BINARY_NAME);
+    let wrapper = bin_dir.join(WRAPPER_NAME);
+
+    let mut updated = false;
+
+    if !same_file_path(&current_exe, &installed_binary) {
+        let source = fs::read(&current_exe)
+            .with_context(|| format!("failed to read {}", current_exe.display()))?;
+        let existing = fs::read(&installed_binary).ok();
+        if existing.as_deref() != Some(source.as_slice()) {
+            stage_installed_binary(&bin_dir, &source)?.activate(&current_stamp(), keep_versions)?;
+            updated = true;
+        }
+    }
+
+    let wrapper_contents = render_wrapper_script();
+    let existing_wrapper = fs::read_to_string(&wrapper).ok();
+    if existing_wrapper.as_deref() != Some(wrapper_contents.as_str()) {
+        write_atomically(&wrapper, wrapper_contents.as_bytes())?;
+        set_executable(&wrapper)?;
+        updated = true;
+    }
+
+    if updated {
+        reporter.update(format!(
+            "installed workspace launcher {} at {}",
+            current_stamp(),
+            wrapper.display()
+        ));
+    } else {
+        reporter.update(format!(
+            "workspace launcher already current at {}",
+            wrapper.display()
+        ));
+    }
+
+    Ok(updated)
+}
+
+/// Lists archived launcher stamps (oldest first) and the currently active stamp.
+pub fn list_versions(workspace_root: &Path) -> Result<(Vec<String>, Option<String>)> {
+    let state = load_state(&workspace_root.join(BIN_DIR_NAME))?;
+    Ok((state.history, state.current))
+}
+
+/// Switches `.orchestrator-bin` back to an archived binary.
+///
+/// Without `to`, picks the version installed before the current one.
+pub fn rollback(workspace_root: &Path, to: Option<&str>) -> Result<(Option<String>, String)> {
+    let bin_dir = workspace_root.join(BIN_DIR_NAME);
+    let mut state = load_state(&bin_dir)?;
+
+    let target = match to {
+        Some(stamp) => {
+            if !state.history.iter().any(|s| s == stamp) {
+                bail!(
+                    "no archived launcher {stamp} under {}; available: {}",
+                    bin_dir.join(VERSIONS_DIR_NAME).display(),
+                    state.history.join(", ")
+                );
+            }
+            stamp.to_string()
+        }
+        None => {
+            let current_index = state
+                .current
+                .as_ref()
+                .and_then(|current| state.history.iter().position(|s| s == current))
+                .unwrap_or(state.history.len());
+            match current_index.checked_sub(1) {
+                Some(i) => state.history[i].clone(),
+                None => bail!("no earlier launcher version to roll back to"),
+            }
+        }
+    };
+
+    let archived = versioned_binary_path(&bin_dir, &target);
+    let contents =
+        fs::read(&archived).with_context(|| format!("failed to read {}", archived.display()))?;
+    stage_installed_binary(&bin_dir, &contents)?.swap_in()?;
+
+    let previous = state.current.replace(target.clone());
+    save_state(&bin_dir, &state)?;
+    Ok((previous, target))
+}
+
+fn versioned_binary_path(bin_dir: &Path, stamp: &str) -> PathBuf {
+    bin_dir
+        .join(VERSIONS_DIR_NAME)
+        .join(format!("{WRAPPER_NAME}-{stamp}"))
+}
+
+fn load_state(bin_dir: &Path) -> Result<LauncherState> {
+    let path = bin_dir.join(STATE_FILE_NAME);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(LauncherState::default()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn save_state(bin_dir: &Path, state: &LauncherState) -> Result<()> {
+    let path = bin_dir.join(STATE_FILE_NAME);
+    let text = serde_json::to_string_pretty(state).context("failed to serialize launcher state")?;
+    write_atomically(&path, text.as_bytes())
+}
+
+/// Writes `contents` to a sibling temp file and renames it over `path`.
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut staging = path.as_os_str().to_os_string();
+    staging.push(format!(".tmp-{}", std::process::id()));
+    let staging = PathBuf::from(staging);
+
+    fs::write(&staging, contents)
+        .with_context(|| format!("failed to write {}", staging.display()))?;
+    fs::rename(&staging, path).with_context(|| {
+        format!(
+            "failed to move {} into place at {}",
+            staging.display(),
+            path.display()
+        )
+    })
+}
+
+fn render_wrapper_script() -> String {
+    format!(
+        "#!/usr/bin/env sh\nset -e\nSCRIPT_DIR=\"$(CDPATH= cd -- \"$(dirname \"$0\")\" && pwd)\"\nexec \"$SCRIPT_DIR/{}\" \"$@\"\n",
+        INSTALLED_BINARY_NAME
+    )
+}
+
+fn same_file_path(left: &Path, right: &Path) -> bool {
+    match (fs::canonicalize(left), fs::canonicalize(right)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => left == right,
+    }
+}
+
+pub fn set_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut perms = fs::metadata(path)
+            .with_context(|| format!("failed to read metadata for {}", path.display()))?
+            .permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(path, perms)
+            .with_context(|| format!("failed to set permissions on {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// A new launcher binary written next to `.orchestrator-bin` but not yet active.
+///
+/// Dropping it without calling [`StagedBinary::activate`] removes the staged file.
+pub struct StagedBinary {
+    path: PathBuf,
+    target: PathBuf,
+    active: bool,
+}
+
+impl StagedBinary {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn swap_in(&mut self) -> Result<()> {
+        fs::rename(&self.path, &self.target).with_context(|| {
+            format!(
+                "failed to move {} into place at {}",
+                self.path.display(),
+                self.target.display()
+            )
+        })?;
+        self.active = true;
+        Ok(())
+    }
+
+    /// Archives the staged binary as `stamp`, then atomically renames it over
+    /// `.orchestrator-bin`, so an interrupted update never leaves a truncated launcher.
+    ///
+    /// Keeps the `keep_versions` most recent archived binaries besides the active one.
+    pub fn activate(mut self, stamp: &str, keep_versions: usize) -> Result<PathBuf> {
+        let bin_dir = self
+            .target
+            .parent()
+            .map(Path::to_path_buf)
+            .context("launcher binary has no parent directory")?;
+        let versions_dir = bin_dir.join(VERSIONS_DIR_NAME);
+        fs::create_dir_all(&versions_dir)
+            .with_context(|| format!("failed to create {}", versions_dir.display()))?;
+
+        let mut state = load_state(&bin_dir)?;
+
+        // Launchers installed before versioning existed have no stamp; keep them
+        // around so the first stamped install can still be rolled back.
+        if state.current.is_none() && self.target.is_file() {
+            let legacy = versioned_binary_path(&bin_dir, LEGACY_STAMP);
+            fs::copy(&self.target, &legacy)
+                .with_context(|| format!("failed to archive {}", self.target.display()))?;
+            state.history.push(LEGACY_STAMP.to_string());
+        }
+
+        let archived = versioned_binary_path(&bin_dir, stamp);
+        let contents = fs::read(&self.path)
+            .with_context(|| format!("failed to read {}", self.path.display()))?;
+        write_atomically(&archived, &contents)?;
+        set_executable(&archived)?;
+
+        self.swap_in()?;
+
+        state.history.retain(|s| s != stamp);
+        state.history.push(stamp.to_string());
+        state.current = Some(stamp.to_string());
+
+        let excess = state.history.len().saturating_sub(keep_versions + 1);
+        for pruned in state.history.drain(..excess) {
+            let _ = fs::remove_file(versioned_binary_path(&bin_dir, &pruned));
+        }
+
+        save_state(&bin_dir, &state)?;
+        Ok(self.target.clone())
+    }
+}
+
+impl Drop for StagedBinary {
+    fn drop(&mut self) {
+        if !self.active {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn stage_installed_binary(bin_dir: &Path, contents: &[u8]) -> Result<StagedBinary> {
+    fs::create_dir_all(bin_dir)
+        .with_context(|| format!("failed to create {}", bin_dir.display()))?;
+
+    let target = bin_dir.join(INSTALLED_BINARY_NAME);
+    let path = bin_dir.join(format!(
+        "{}.tmp-{}",
+        INSTALLED_BINARY_NAME,
+        std::process::id()
+    ));
+
+    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+    let staged = StagedBinary {
+        path,
+        target,
+        active: false,
+    };
+    set_executable(staged.path())?;
+    Ok(staged)
+}
+
+/// Runs `binary --version` and returns its trimmed output.
+pub fn binary_version(binary: &Path) -> Result<String> {
+    let output = Command::new(binary)
+        .arg("--version")
+        .output()
+        .with_context(|| format!("failed to run {} --version", binary.display()))?;
+
+    if !output.status.success() {
+        bail!(
+            "{} --version failed (exit={}): {}",
+            binary.display(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -1,12 +1,30 @@
+mod arch;
+mod asset_check;
+mod build_info;
+mod cancel;
+mod changelog;
+mod checksum;
+mod civil_date;
 mod git;
+mod git_backend;
 mod github;
+mod iso_smoke;
+mod launcher;
 mod monitor;
+mod release_definition;
+mod release_history;
 mod release_iso;
+mod release_notes;
+mod release_promote;
+mod release_stage;
 mod reporter;
+mod self_update;
 mod tui;
+mod version_bump;
 mod workspace_update;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use cancel::CancelToken;
 use clap::{Parser, Subcommand};
 use reporter::{DynReporter, PlainReporter};
 use std::path::PathBuf;
@@ -15,6 +33,7 @@ use std::{io::IsTerminal, sync::Arc};
 
 #[derive(Parser, Debug)]
 #[command(name = "orchestrator")]
+#[command(version = build_info::LONG_VERSION)]
 #[command(about = "Admin tools for the TruthDB organization")]
 struct Cli {
     /// Disable the ratatui UI (use plain stderr output).
@@ -25,6 +44,10 @@ struct Cli {
     #[arg(long, default_value_t = false)]
     auto_exit: bool,
 
+    /// Implementation used for read-only git queries (status, rev-parse, tags, history, file contents).
+    #[arg(long, value_enum, default_value_t = git_backend::BackendKind::default())]
+    git_backend: git_backend::BackendKind,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -40,6 +63,72 @@ enum Commands {
         /// GitHub org/owner used for cloning missing repos.
         #[arg(long, default_value = "Truthdb")]
         owner: String,
+
+        /// Overwrite workspace files that were modified locally (a `.orig` backup is kept).
+        #[arg(long, default_value_t = false)]
+        force: bool,
+
+        /// Protocol used for cloning missing repos from GitHub (overrides the manifest).
+        #[arg(long, value_enum, conflicts_with = "clone_url")]
+        protocol: Option<workspace_update::CloneProtocol>,
+
+        /// Clone URL template for missing repos (overrides the manifest).
+        ///
+        /// Placeholders: {owner}, {repo}. Examples:
+        /// https://github.com/{owner}/{repo}.git, file:///srv/mirrors/{repo}.git
+        #[arg(long)]
+        clone_url: Option<String>,
+
+        /// Maximum number of repos cloned concurrently.
+        #[arg(long, default_value_t = 4)]
+        jobs: usize,
+
+        /// Create shallow clones with this many commits of history.
+        #[arg(long)]
+        depth: Option<u32>,
+
+        /// Partial clone filter passed to `git clone --filter` (e.g. blob:none).
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Number of previous launcher binaries to keep under .bin/versions/.
+        #[arg(long, default_value_t = launcher::DEFAULT_KEEP_VERSIONS)]
+        keep_versions: usize,
+    },
+
+    /// Replace the workspace launcher binary with a newer orchestrator build.
+    ///
+    /// Builds the sibling orchestrator/ checkout (or downloads the latest release),
+    /// verifies the new binary runs, and atomically swaps `.bin/.orchestrator-bin`.
+    SelfUpdate {
+        /// Workspace root directory containing the sibling repos.
+        #[arg(long)]
+        workspace_root: Option<PathBuf>,
+
+        /// GitHub org/owner used for downloading releases.
+        #[arg(long, default_value = "Truthdb")]
+        owner: String,
+
+        /// Where to get the new binary from.
+        #[arg(long, value_enum, default_value_t = self_update::SelfUpdateSource::Build)]
+        source: self_update::SelfUpdateSource,
+
+        /// Number of previous launcher binaries to keep under .bin/versions/.
+        #[arg(long, default_value_t = launcher::DEFAULT_KEEP_VERSIONS)]
+        keep_versions: usize,
+    },
+
+    /// Print build metadata (version, git SHA, build date, embedded manifest hash).
+    Version {
+        /// Print machine-readable JSON.
+        #[arg(long, default_value_t = false)]
+        json: bool,
+    },
+
+    /// Inspect or roll back the workspace launcher binary.
+    Launcher {
+        #[command(subcommand)]
+        command: LauncherCommand,
     },
 
     /// Tag and release all dependencies needed to produce an installer ISO.
@@ -50,13 +139,29 @@ enum Commands {
         /// Version/tag to create (SemVer).
         ///
         /// Examples: v1.2.3, 1.2.3, v1.2.3-rc.1, 1.2.3-rc.1
-        #[arg(long)]
-        version: String,
+        #[arg(long, required_unless_present = "bump")]
+        version: Option<String>,
+
+        /// Derive the version by bumping the highest existing release tag across the ISO repos.
+        #[arg(long, value_enum, conflicts_with_all = ["version", "resume"])]
+        bump: Option<version_bump::BumpKind>,
+
+        /// Don't ask for confirmation of the release plan.
+        #[arg(long, default_value_t = false)]
+        yes: bool,
 
         /// Directory containing the sibling repos (truthdb/, installer/, installer-kernel/, installer-iso/).
         #[arg(long)]
         repos_root: Option<PathBuf>,
 
+        /// Release definition TOML to use instead of the embedded release/iso.toml.
+        #[arg(long)]
+        release_definition: Option<PathBuf>,
+
+        /// Target architecture whose assets the release must publish (repeatable).
+        #[arg(long = "arch", value_enum, default_value = "x86_64")]
+        arches: Vec<arch::Arch>,
+
         /// GitHub org/owner.
         #[arg(long, default_value = "Truthdb")]
         owner: String,
@@ -65,20 +170,178 @@ enum Commands {
         #[arg(long, default_value_t = false)]
         dry_run: bool,
 
+        /// Rewrite version files that disagree with --version and commit them (nothing is tagged).
+        #[arg(long, default_value_t = false)]
+        fix: bool,
+
+        /// Tag even if the latest ci.yml run for HEAD is missing, pending, or failed.
+        #[arg(long, default_value_t = false)]
+        allow_red_ci: bool,
+
+        /// After the release, download every chain asset into <DIR>/<tag>/ with a manifest.json.
+        #[arg(long)]
+        download_dir: Option<PathBuf>,
+
+        /// Boot the downloaded x86_64 ISO under QEMU/OVMF and check the serial console (needs
+        /// --download-dir and --arch x86_64; other arches' ISOs are only checked statically).
+        #[arg(long, default_value_t = false, requires = "download_dir")]
+        smoke_test: bool,
+
+        /// Tag a specific commit or origin branch for one repo instead of HEAD, as
+        /// <repo>=<sha|branch> (repeatable). The checkout is left alone.
+        #[arg(long, value_name = "REPO=REV")]
+        at: Vec<String>,
+
         /// Resume a partially completed release (skip tag creation/push for repos
         /// that already have the tag on origin, but still poll assets and continue).
         #[arg(long, default_value_t = false)]
         resume: bool,
 
+        /// Create GPG/SSH-signed tags (`git tag -s`) and verify them before pushing.
+        #[arg(long, default_value_t = false)]
+        sign: bool,
+
+        /// Open $EDITOR to review each generated tag message before tagging (implies --no-tui).
+        #[arg(long, default_value_t = false)]
+        edit_tag_message: bool,
+
         /// Poll interval in seconds.
         #[arg(long, default_value_t = 10)]
         poll_interval_secs: u64,
 
-        /// Timeout in seconds per repo while waiting for release assets.
+        /// Timeout in seconds per repo for its release workflow and assets together.
         #[arg(long, default_value_t = 45 * 60)]
         timeout_secs: u64,
     },
 
+    /// Promote a tested release candidate to the final release.
+    ///
+    /// Tags the exact commits behind the candidate's tags with the final version in every
+    /// ISO repo, then waits for each release like release-iso does.
+    ReleasePromote {
+        /// Release candidate to promote, e.g. v1.2.3-rc.2.
+        #[arg(long)]
+        from: String,
+
+        /// Final version to create (default: --from without its prerelease part).
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Directory containing the sibling repos (truthdb/, installer/, installer-kernel/, installer-iso/).
+        #[arg(long)]
+        repos_root: Option<PathBuf>,
+
+        /// Release definition TOML whose version files and `depends_on` are used, instead of
+        /// the embedded release/iso.toml.
+        #[arg(long)]
+        release_definition: Option<PathBuf>,
+
+        /// Target architecture whose assets the release must publish (repeatable).
+        #[arg(long = "arch", value_enum, default_value = "x86_64")]
+        arches: Vec<arch::Arch>,
+
+        /// GitHub org/owner.
+        #[arg(long, default_value = "Truthdb")]
+        owner: String,
+
+        /// Don't create or push tags; just print what would happen.
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Continue a partial promotion (repos already tagged on the candidate commit are skipped).
+        #[arg(long, default_value_t = false)]
+        resume: bool,
+
+        /// Create GPG/SSH-signed tags (`git tag -s`) and verify them before pushing.
+        #[arg(long, default_value_t = false)]
+        sign: bool,
+
+        /// Poll interval in seconds.
+        #[arg(long, default_value_t = 10)]
+        poll_interval_secs: u64,
+
+        /// Timeout in seconds per repo for its release workflow and assets together.
+        #[arg(long, default_value_t = 45 * 60)]
+        timeout_secs: u64,
+    },
+
+    /// Boot an installer ISO headless under QEMU/OVMF and check its serial console.
+    ///
+    /// Succeeds once a success marker from the release definition (or --marker) appears.
+    IsoSmoke {
+        /// Installer ISO to boot.
+        #[arg(long)]
+        iso: PathBuf,
+
+        /// Release definition TOML to use instead of the embedded release/iso.toml.
+        #[arg(long)]
+        release_definition: Option<PathBuf>,
+
+        /// QEMU binary (default qemu-system-x86_64).
+        #[arg(long)]
+        qemu: Option<String>,
+
+        /// OVMF firmware image; defaults to the first one found in the usual distro paths.
+        #[arg(long)]
+        ovmf: Option<PathBuf>,
+
+        /// Serial console text that means the boot succeeded (repeatable; overrides the definition).
+        #[arg(long = "marker")]
+        markers: Vec<String>,
+
+        /// Give up after this many seconds (overrides the definition).
+        #[arg(long)]
+        timeout_secs: Option<u64>,
+
+        /// Where to write the captured serial console (default: <iso>.serial.log).
+        #[arg(long)]
+        serial_log: Option<PathBuf>,
+    },
+
+    /// Render combined Markdown release notes for every repo in an ISO release.
+    ///
+    /// For each repo, lists the commits between the previous release tag and the
+    /// given version's tag. Prints to stdout unless --output is given.
+    ReleaseNotes {
+        /// Released version/tag (SemVer), e.g. v1.2.3.
+        #[arg(long)]
+        version: String,
+
+        /// Directory containing the sibling repos (truthdb/, installer/, installer-kernel/, installer-iso/).
+        #[arg(long)]
+        repos_root: Option<PathBuf>,
+
+        /// GitHub org/owner.
+        #[arg(long, default_value = "Truthdb")]
+        owner: String,
+
+        /// Write the notes to this file instead of stdout.
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Also set the notes as the body of the installer-iso GitHub release.
+        #[arg(long, default_value_t = false)]
+        publish: bool,
+    },
+
+    /// Summarize how long past release-iso runs took, per repo and overall.
+    ///
+    /// Stage durations run from a repo's tag push until its release assets were ready.
+    ReleaseHistory {
+        /// Directory containing the sibling repos (truthdb/, installer/, installer-kernel/, installer-iso/).
+        #[arg(long)]
+        repos_root: Option<PathBuf>,
+
+        /// Release definition TOML whose `depends_on` the expected total follows, instead of
+        /// the embedded release/iso.toml.
+        #[arg(long)]
+        release_definition: Option<PathBuf>,
+
+        /// Number of recent runs to list.
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+
     /// Show a live organization monitor dashboard.
     ///
     /// This command does not perform any actions; it only displays status.
@@ -93,10 +356,49 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum LauncherCommand {
+    /// List archived launcher binaries.
+    List {
+        /// Workspace root directory containing the sibling repos.
+        #[arg(long)]
+        workspace_root: Option<PathBuf>,
+    },
+
+    /// Switch the launcher back to a previously installed binary.
+    Rollback {
+        /// Workspace root directory containing the sibling repos.
+        #[arg(long)]
+        workspace_root: Option<PathBuf>,
+
+        /// Version stamp to switch to (see `launcher list`). Defaults to the previous one.
+        #[arg(long)]
+        to: Option<String>,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    git_backend::init(cli.git_backend)?;
+
+    // Version output goes to stdout for scripting, so it never starts the TUI.
+    if let Commands::Version { json } = cli.command {
+        let info = build_info::current();
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&info).context("failed to serialize build info")?
+            );
+        } else {
+            println!("{}", info.render_plain());
+        }
+        return Ok(());
+    }
 
-    let use_tui = !cli.no_tui && std::io::stdout().is_terminal() && std::io::stderr().is_terminal();
+    let use_tui = !cli.no_tui
+        && !needs_plain_terminal(&cli.command)
+        && std::io::stdout().is_terminal()
+        && std::io::stderr().is_terminal();
 
     if use_tui {
         let (tx, rx) = crossbeam_channel::unbounded();
@@ -107,14 +409,14 @@ fn main() -> Result<()> {
         );
         reporter.ok("OK".to_string());
 
-        let shutdown = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let cancel = CancelToken::new();
 
         // Move the command into a worker thread so the UI can stay responsive.
         let command = cli.command;
         let worker = std::thread::spawn({
             let reporter = reporter.clone();
             let tx = tx.clone();
-            let shutdown = shutdown.clone();
+            let cancel = cancel.clone();
             move || {
                 let is_monitor = matches!(&command, Commands::Monitor { .. });
 
@@ -129,9 +431,9 @@ fn main() -> Result<()> {
                         },
                         tx.clone(),
                         reporter.clone(),
-                        shutdown,
+                        cancel,
                     ),
-                    other => run_command(other, reporter.clone()),
+                    other => run_command(other, reporter.clone(), cancel),
                 };
 
                 if let Err(ref e) = result {
@@ -154,8 +456,8 @@ fn main() -> Result<()> {
         // Run the UI loop on the main thread.
         let ui_res = tui::run(rx, cli.auto_exit);
 
-        // Tell long-running workers (monitor) to stop.
-        shutdown.store(true, std::sync::atomic::Ordering::SeqCst);
+        // Tell the worker to stop; long-running commands check this between steps.
+        cancel.cancel();
 
         // Ensure worker has completed; if it errored, print a normal error after UI teardown.
         let worker_res = match worker.join() {
@@ -176,39 +478,189 @@ fn main() -> Result<()> {
     }
 
     let reporter: DynReporter = Arc::new(PlainReporter::new());
-    run_command(cli.command, reporter)
+    run_command(cli.command, reporter, CancelToken::new())
+}
+
+/// Commands that hand the terminal to another program (e.g. $EDITOR) or write their
+/// result to stdout can't run under the TUI.
+fn needs_plain_terminal(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::ReleaseIso {
+            edit_tag_message: true,
+            ..
+        } | Commands::ReleaseNotes { output: None, .. }
+    )
 }
 
-fn run_command(command: Commands, reporter: DynReporter) -> Result<()> {
+fn run_command(command: Commands, reporter: DynReporter, cancel: CancelToken) -> Result<()> {
     match command {
         Commands::WorkspaceUpdate {
             workspace_root,
             owner,
+            force,
+            protocol,
+            clone_url,
+            jobs,
+            depth,
+            filter,
+            keep_versions,
         } => workspace_update::run(
             workspace_update::WorkspaceUpdateArgs {
                 workspace_root,
                 owner,
+                force,
+                protocol,
+                clone_url,
+                jobs,
+                clone_options: git::CloneOptions { depth, filter },
+                keep_versions,
+                cancel,
             },
             reporter,
         ),
 
+        Commands::SelfUpdate {
+            workspace_root,
+            owner,
+            source,
+            keep_versions,
+        } => self_update::run(
+            self_update::SelfUpdateArgs {
+                workspace_root,
+                owner,
+                source,
+                keep_versions,
+            },
+            reporter,
+        ),
+
+        Commands::Launcher { command } => run_launcher_command(command, reporter),
+
+        Commands::Version { .. } => unreachable!("version is handled before dispatch"),
+
         Commands::ReleaseIso {
             version,
+            bump,
+            yes,
             repos_root,
+            release_definition,
+            arches,
             owner,
             dry_run,
+            fix,
+            allow_red_ci,
+            download_dir,
+            smoke_test,
+            at,
             resume,
+            sign,
+            edit_tag_message,
             poll_interval_secs,
             timeout_secs,
         } => release_iso::run(
             release_iso::ReleaseIsoArgs {
                 version,
+                bump,
+                yes,
                 repos_root,
+                release_definition,
+                arches,
                 owner,
                 dry_run,
+                fix,
+                allow_red_ci,
+                download_dir,
+                smoke_test,
+                at,
                 resume,
+                sign,
+                edit_tag_message,
                 poll_interval: Duration::from_secs(poll_interval_secs),
                 timeout: Duration::from_secs(timeout_secs),
+                cancel,
+            },
+            reporter,
+        ),
+
+        Commands::ReleasePromote {
+            from,
+            to,
+            repos_root,
+            release_definition,
+            arches,
+            owner,
+            dry_run,
+            resume,
+            sign,
+            poll_interval_secs,
+            timeout_secs,
+        } => release_promote::run(
+            release_promote::ReleasePromoteArgs {
+                from,
+                to,
+                repos_root,
+                release_definition,
+                arches,
+                owner,
+                dry_run,
+                resume,
+                sign,
+                poll_interval: Duration::from_secs(poll_interval_secs),
+                timeout: Duration::from_secs(timeout_secs),
+                cancel,
+            },
+            reporter,
+        ),
+
+        Commands::IsoSmoke {
+            iso,
+            release_definition,
+            qemu,
+            ovmf,
+            markers,
+            timeout_secs,
+            serial_log,
+        } => iso_smoke::run(
+            iso_smoke::IsoSmokeArgs {
+                iso,
+                release_definition,
+                qemu,
+                ovmf,
+                markers,
+                timeout: timeout_secs.map(Duration::from_secs),
+                serial_log,
+                cancel,
+            },
+            reporter,
+        ),
+
+        Commands::ReleaseNotes {
+            version,
+            repos_root,
+            owner,
+            output,
+            publish,
+        } => release_notes::run(
+            release_notes::ReleaseNotesArgs {
+                version,
+                repos_root,
+                owner,
+                output,
+                publish,
+            },
+            reporter,
+        ),
+
+        Commands::ReleaseHistory {
+            repos_root,
+            release_definition,
+            limit,
+        } => release_history::run(
+            release_history::ReleaseHistoryArgs {
+                repos_root,
+                release_definition,
+                limit,
             },
             reporter,
         ),
@@ -220,3 +672,49 @@ fn run_command(command: Commands, reporter: DynReporter) -> Result<()> {
         }
     }
 }
+
+fn run_launcher_command(command: LauncherCommand, reporter: DynReporter) -> Result<()> {
+    match command {
+        LauncherCommand::List { workspace_root } => {
+            let workspace_root = workspace_update::resolve_workspace_root(workspace_root)?;
+            let (history, current) = launcher::list_versions(&workspace_root)?;
+            let lines = history
+                .iter()
+                .rev()
+                .map(|stamp| {
+                    let marker = if current.as_deref() == Some(stamp.as_str()) {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    format!("{marker} {stamp}")
+                })
+                .collect::<Vec<_>>();
+            reporter.step(
+                "Launcher Versions".to_string(),
+                if lines.is_empty() {
+                    "no archived launcher binaries".to_string()
+                } else {
+                    lines.join("\n")
+                },
+            );
+            reporter.ok("OK".to_string());
+            Ok(())
+        }
+
+        LauncherCommand::Rollback { workspace_root, to } => {
+            let workspace_root = workspace_update::resolve_workspace_root(workspace_root)?;
+            reporter.step(
+                "Launcher Rollback".to_string(),
+                format!("workspace_root={}", workspace_root.display()),
+            );
+            let (previous, current) = launcher::rollback(&workspace_root, to.as_deref())?;
+            reporter.ok(format!(
+                "{} -> {}",
+                previous.as_deref().unwrap_or("unknown"),
+                current
+            ));
+            Ok(())
+        }
+    }
+}
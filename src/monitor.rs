@@ -1,18 +1,13 @@
-use std::{
-    sync::{
-        Arc,
-        atomic::{AtomicBool, Ordering},
-    },
-    time::Duration,
-};
+use std::time::Duration;
 
 use anyhow::Result;
 use crossbeam_channel::Sender;
 
 use crate::{
+    cancel::CancelToken,
     github::{
-        FALLBACK_GITHUB_TOKEN_ENV, GitHub, LEGACY_GITHUB_TOKEN_ENV, PRIMARY_GITHUB_TOKEN_ENV,
-        github_token,
+        CI_WORKFLOW_FILE, FALLBACK_GITHUB_TOKEN_ENV, GitHub, LEGACY_GITHUB_TOKEN_ENV,
+        PRIMARY_GITHUB_TOKEN_ENV, github_token,
     },
     reporter::DynReporter,
     tui::{ActionState, RepoStatusRow, UiEvent},
@@ -36,13 +31,11 @@ const REPOS: [&str; 9] = [
     "website",
 ];
 
-const CI_WORKFLOW_FILE: &str = "ci.yml";
-
 pub fn run(
     args: MonitorArgs,
     tx: Sender<UiEvent>,
     reporter: DynReporter,
-    shutdown: Arc<AtomicBool>,
+    cancel: CancelToken,
 ) -> Result<()> {
     reporter.step(
         "Monitor".to_string(),
@@ -74,15 +67,8 @@ pub fn run(
     let _ = tx.send(UiEvent::SetRepos { rows: rows.clone() });
     refresh_rows_incremental(&gh, &mut rows, &tx, reporter.as_ref(), true)?;
 
-    while !shutdown.load(Ordering::SeqCst) {
-        let mut slept = Duration::ZERO;
-        while slept < args.poll_interval && !shutdown.load(Ordering::SeqCst) {
-            let step = Duration::from_millis(200);
-            std::thread::sleep(step);
-            slept += step;
-        }
-
-        if shutdown.load(Ordering::SeqCst) {
+    while !cancel.is_cancelled() {
+        if cancel.sleep(args.poll_interval, "monitor").is_err() {
             break;
         }
 
@@ -0,0 +1,482 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const EMBEDDED_DEFINITION: &str = include_str!("../release/iso.toml");
+
+/// Per-repo release rules for `release-iso`, from `release/iso.toml` or `--release-definition`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReleaseDefinition {
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoDefinition>,
+    #[serde(default)]
+    pub smoke_test: SmokeTestDefinition,
+    #[serde(default)]
+    pub asset_checks: Vec<AssetCheck>,
+}
+
+/// A content check for downloaded release assets whose file name matches `pattern`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AssetCheck {
+    /// File name pattern; `*` matches any run of characters.
+    pub pattern: String,
+    pub kind: AssetCheckKind,
+    /// Archive entries (by file name or path) or ISO paths that must be present.
+    #[serde(default)]
+    pub contains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AssetCheckKind {
+    /// A gzip-compressed tarball that reads to the end without errors.
+    TarGz,
+    /// A PE/COFF EFI image whose sections all fit in the file.
+    Pe,
+    /// An ISO 9660 image with a bootable El Torito EFI entry.
+    Iso,
+}
+
+impl fmt::Display for AssetCheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TarGz => "tar-gz",
+            Self::Pe => "pe",
+            Self::Iso => "iso",
+        })
+    }
+}
+
+impl AssetCheck {
+    pub fn matches(&self, name: &str) -> bool {
+        let mut parts = self.pattern.split('*');
+        let Some(mut rest) = name.strip_prefix(parts.next().unwrap_or_default()) else {
+            return false;
+        };
+        let parts: Vec<&str> = parts.collect();
+        let Some((last, middle)) = parts.split_last() else {
+            return rest.is_empty();
+        };
+        for part in middle {
+            match rest.find(part) {
+                Some(at) => rest = &rest[at + part.len()..],
+                None => return false,
+            }
+        }
+        rest.ends_with(last)
+    }
+}
+
+/// Defaults for booting the installer ISO under QEMU.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmokeTestDefinition {
+    #[serde(default)]
+    pub success_markers: Vec<String>,
+    #[serde(default)]
+    pub failure_markers: Vec<String>,
+    #[serde(default = "default_smoke_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_smoke_memory_mb")]
+    pub memory_mb: u32,
+}
+
+impl Default for SmokeTestDefinition {
+    fn default() -> Self {
+        Self {
+            success_markers: Vec::new(),
+            failure_markers: Vec::new(),
+            timeout_secs: default_smoke_timeout_secs(),
+            memory_mb: default_smoke_memory_mb(),
+        }
+    }
+}
+
+fn default_smoke_timeout_secs() -> u64 {
+    600
+}
+
+fn default_smoke_memory_mb() -> u32 {
+    2048
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RepoDefinition {
+    #[serde(default)]
+    pub version_files: Vec<VersionFile>,
+    /// Repos whose releases must be published before this one is tagged.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// A file that records the repo's version.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VersionFile {
+    pub path: PathBuf,
+    /// Dotted TOML key such as `package.version`; `None` means the file holds only the version.
+    pub key: Option<String>,
+}
+
+/// A version file whose contents disagree with the release version.
+#[derive(Debug, Clone)]
+pub struct VersionMismatch {
+    pub file: VersionFile,
+    pub found: String,
+}
+
+impl ReleaseDefinition {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .with_context(|| format!("failed to read {}", path.display()))?;
+                toml::from_str(&text).with_context(|| format!("failed to parse {}", path.display()))
+            }
+            None => toml::from_str(EMBEDDED_DEFINITION)
+                .context("failed to parse embedded release definition"),
+        }
+    }
+
+    pub fn repo(&self, name: &str) -> Option<&RepoDefinition> {
+        self.repos.get(name)
+    }
+
+    pub fn version_files(&self, name: &str) -> &[VersionFile] {
+        self.repo(name)
+            .map(|repo| repo.version_files.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn checks_for(&self, asset: &str) -> Vec<&AssetCheck> {
+        self.asset_checks
+            .iter()
+            .filter(|check| check.matches(asset))
+            .collect()
+    }
+
+    pub fn depends_on(&self, name: &str) -> &[String] {
+        self.repo(name)
+            .map(|repo| repo.depends_on.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Checks that the `depends_on` of `repos` only name other `repos` and form no cycle.
+    pub fn check_dependencies(&self, repos: &[&str]) -> Result<()> {
+        for repo in repos {
+            for dependency in self.depends_on(repo) {
+                if !repos.contains(&dependency.as_str()) {
+                    bail!(
+                        "{repo} depends on unknown repo '{dependency}' (expected one of: {})",
+                        repos.join(", ")
+                    );
+                }
+            }
+        }
+
+        // Peel off repos whose dependencies are all resolved; whatever is left is a cycle.
+        let mut resolved: Vec<&str> = Vec::new();
+        while resolved.len() < repos.len() {
+            let ready: Vec<&str> = repos
+                .iter()
+                .copied()
+                .filter(|repo| !resolved.contains(repo))
+                .filter(|repo| {
+                    self.depends_on(repo)
+                        .iter()
+                        .all(|dependency| resolved.contains(&dependency.as_str()))
+                })
+                .collect();
+            if ready.is_empty() {
+                let cycle: Vec<&str> = repos
+                    .iter()
+                    .copied()
+                    .filter(|repo| !resolved.contains(repo))
+                    .collect();
+                bail!("release dependency cycle among: {}", cycle.join(", "));
+            }
+            resolved.extend(ready);
+        }
+        Ok(())
+    }
+}
+
+impl VersionFile {
+    fn describe(&self) -> String {
+        match &self.key {
+            Some(key) => format!("{} ({key})", self.path.display()),
+            None => self.path.display().to_string(),
+        }
+    }
+
+    /// Extracts the version string from this file's `text`.
+    pub fn version_in(&self, text: &str) -> Result<String> {
+        let path = &self.path;
+        let Some(key) = &self.key else {
+            return Ok(text.trim().to_string());
+        };
+
+        let doc: toml::Value =
+            toml::from_str(text).with_context(|| format!("failed to parse {}", path.display()))?;
+        let mut value = &doc;
+        for part in key.split('.') {
+            value = value
+                .get(part)
+                .with_context(|| format!("{} has no key {key}", path.display()))?;
+        }
+        value
+            .as_str()
+            .map(str::to_string)
+            .with_context(|| format!("{key} in {} is not a string", path.display()))
+    }
+
+    /// Rewrites the version in place, preserving the rest of the file.
+    ///
+    /// Returns every file changed, relative to the repo (a Cargo.lock may come along
+    /// with a Cargo.toml `package.version`).
+    pub fn write(&self, repo_dir: &Path, version: &str) -> Result<Vec<PathBuf>> {
+        let path = repo_dir.join(&self.path);
+
+        let Some(key) = &self.key else {
+            fs::write(&path, format!("{version}\n"))
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            return Ok(vec![self.path.clone()]);
+        };
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut doc: toml_edit::DocumentMut = text
+            .parse()
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+
+        let parts: Vec<&str> = key.split('.').collect();
+        let (last, parents) = parts.split_last().context("empty version key")?;
+        let mut table = doc.as_item_mut();
+        for part in parents {
+            table = table
+                .get_mut(part)
+                .with_context(|| format!("{} has no key {key}", path.display()))?;
+        }
+        let item = table
+            .get_mut(last)
+            .with_context(|| format!("{} has no key {key}", path.display()))?;
+        if !item.is_str() {
+            bail!("{key} in {} is not a string", path.display());
+        }
+        let decor = item.as_value().map(|value| value.decor().clone());
+        *item = toml_edit::value(version);
+        if let (Some(decor), Some(value)) = (decor, item.as_value_mut()) {
+            *value.decor_mut() = decor;
+        }
+
+        fs::write(&path, doc.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        let mut changed = vec![self.path.clone()];
+        if self
+            .path
+            .file_name()
+            .is_some_and(|name| name == "Cargo.toml")
+            && key == "package.version"
+            && let Some(name) = doc
+                .get("package")
+                .and_then(|package| package.get("name"))
+                .and_then(|name| name.as_str())
+        {
+            let lock = self.path.with_file_name("Cargo.lock");
+            if update_cargo_lock(&repo_dir.join(&lock), name, version)? {
+                changed.push(lock);
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// Updates the version of the local (source-less) package `name` in a Cargo.lock.
+fn update_cargo_lock(path: &Path, name: &str, version: &str) -> Result<bool> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let mut doc: toml_edit::DocumentMut = text
+        .parse()
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+
+    let mut updated = false;
+    if let Some(packages) = doc
+        .get_mut("package")
+        .and_then(|item| item.as_array_of_tables_mut())
+    {
+        for package in packages.iter_mut() {
+            let is_local = package.get("name").and_then(|n| n.as_str()) == Some(name)
+                && !package.contains_key("source");
+            if is_local {
+                package["version"] = toml_edit::value(version);
+                updated = true;
+            }
+        }
+    }
+
+    if updated {
+        fs::write(path, doc.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+    Ok(updated)
+}
+
+/// Version files that don't record `version` (build metadata is ignored).
+///
+/// `read` returns a file's contents given its repo-relative path, so callers can check the
+/// working tree or a specific commit.
+pub fn check_version_files(
+    files: &[VersionFile],
+    version: &semver::Version,
+    read: impl Fn(&Path) -> Result<String>,
+) -> Result<Vec<VersionMismatch>> {
+    let mut mismatches = Vec::new();
+    for file in files {
+        let found = file.version_in(&read(&file.path)?)?;
+        let matches = semver::Version::parse(&found).is_ok_and(|parsed| {
+            (parsed.major, parsed.minor, parsed.patch, &parsed.pre)
+                == (version.major, version.minor, version.patch, &version.pre)
+        });
+        if !matches {
+            mismatches.push(VersionMismatch {
+                file: file.clone(),
+                found,
+            });
+        }
+    }
+    Ok(mismatches)
+}
+
+impl VersionMismatch {
+    pub fn describe(&self) -> String {
+        format!("{} is {}", self.file.describe(), self.found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(pattern: &str) -> AssetCheck {
+        AssetCheck {
+            pattern: pattern.to_string(),
+            kind: AssetCheckKind::TarGz,
+            contains: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn asset_check_patterns() {
+        assert!(check("BOOTX64.EFI").matches("BOOTX64.EFI"));
+        assert!(!check("BOOTX64.EFI").matches("BOOTX64.EFI.sha256"));
+        assert!(check("BOOT*.EFI").matches("BOOTAA64.EFI"));
+        assert!(!check("BOOT*.EFI").matches("BOOTAA64.EFI.sha256"));
+
+        let tarball = check("truthdb-v*-linux-gnu.tar.gz");
+        assert!(tarball.matches("truthdb-v1.2.3-x86_64-linux-gnu.tar.gz"));
+        assert!(!tarball.matches("truthdb-v1.2.3-x86_64-linux-gnu.sha256"));
+        assert!(!tarball.matches("truthdb-installer-v1.2.3-x86_64-linux-musl.tar.gz"));
+
+        assert!(check("*").matches("anything"));
+        assert!(check("a*b*c").matches("abc"));
+        assert!(!check("a*b*c").matches("acb"));
+    }
+
+    const REPOS: [&str; 3] = ["kernel", "installer", "iso"];
+
+    fn definition(text: &str) -> ReleaseDefinition {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn dependencies_accept_a_dag() {
+        definition(
+            r#"
+            [repos.iso]
+            depends_on = ["kernel", "installer"]
+            [repos.installer]
+            depends_on = ["kernel"]
+            "#,
+        )
+        .check_dependencies(&REPOS)
+        .unwrap();
+    }
+
+    #[test]
+    fn dependencies_reject_unknown_repo() {
+        let err = definition("[repos.iso]\ndepends_on = [\"kernal\"]")
+            .check_dependencies(&REPOS)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("iso depends on unknown repo 'kernal'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn dependencies_reject_cycles() {
+        let err = definition(
+            r#"
+            [repos.iso]
+            depends_on = ["installer"]
+            [repos.installer]
+            depends_on = ["iso"]
+            "#,
+        )
+        .check_dependencies(&REPOS)
+        .unwrap_err()
+        .to_string();
+        assert_eq!(err, "release dependency cycle among: installer, iso");
+
+        let err = definition("[repos.iso]\ndepends_on = [\"iso\"]")
+            .check_dependencies(&REPOS)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "release dependency cycle among: iso");
+    }
+
+    #[test]
+    fn candidate_version_files_disagree_with_the_final_release() {
+        let files = [VersionFile {
+            path: PathBuf::from("Cargo.toml"),
+            key: Some("package.version".to_string()),
+        }];
+        let read = |_: &Path| Ok("[package]\nversion = \"1.2.3-rc.2\"\n".to_string());
+
+        let rc = semver::Version::parse("1.2.3-rc.2").unwrap();
+        assert!(check_version_files(&files, &rc, read).unwrap().is_empty());
+
+        let final_release = semver::Version::parse("1.2.3").unwrap();
+        let mismatches = check_version_files(&files, &final_release, read).unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].describe(),
+            "Cargo.toml (package.version) is 1.2.3-rc.2"
+        );
+    }
+
+    #[test]
+    fn embedded_definition_checks_every_arch() {
+        let definition = ReleaseDefinition::load(None).unwrap();
+        for asset in [
+            "BOOTX64.EFI",
+            "BOOTAA64.EFI",
+            "truthdb-installer-v1.2.3.iso",
+            "truthdb-installer-v1.2.3-aarch64.iso",
+            "truthdb-v1.2.3-aarch64-linux-gnu.tar.gz",
+        ] {
+            assert_eq!(definition.checks_for(asset).len(), 1, "{asset}");
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use crate::civil_date::civil_date;
+use crate::launcher::BIN_DIR_NAME;
+use crate::release_definition::ReleaseDefinition;
+use crate::release_iso::{ISO_CHAIN_REPOS, default_repos_root};
+use crate::reporter::{DynReporter, Reporter};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE_NAME: &str = "release-history.json";
+/// Runs kept in the history file; older ones are dropped.
+const MAX_RUNS: usize = 100;
+/// Most recent successful stage timings an estimate is based on.
+const ESTIMATE_SAMPLES: usize = 20;
+
+#[derive(Clone, Debug)]
+pub struct ReleaseHistoryArgs {
+    pub repos_root: Option<PathBuf>,
+    pub release_definition: Option<PathBuf>,
+    pub limit: usize,
+}
+
+/// Timings of past `release-iso` runs, kept under the workspace's `.bin/`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReleaseHistory {
+    #[serde(default)]
+    pub runs: Vec<ReleaseRun>,
+}
+
+/// One live `release-iso` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseRun {
+    pub tag: String,
+    /// Unix time the release stages started.
+    pub started_at: u64,
+    pub total_secs: u64,
+    pub ok: bool,
+    pub stages: Vec<StageTiming>,
+}
+
+/// Time from a repo's tag push until its release assets were ready (or it failed).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub repo: String,
+    pub secs: u64,
+    pub ok: bool,
+}
+
+/// Typical duration of a repo's stage, from its recent successful runs.
+#[derive(Debug, Clone, Copy)]
+pub struct StageEstimate {
+    pub median: Duration,
+    pub p95: Duration,
+    pub samples: usize,
+}
+
+impl ReleaseHistory {
+    pub fn path(repos_root: &Path) -> PathBuf {
+        repos_root.join(BIN_DIR_NAME).join(HISTORY_FILE_NAME)
+    }
+
+    pub fn load(repos_root: &Path) -> Result<Self> {
+        let path = Self::path(repos_root);
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("failed to parse {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, repos_root: &Path) -> Result<()> {
+        let path = Self::path(repos_root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let text =
+            serde_json::to_string_pretty(self).context("failed to serialize release history")?;
+        fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn record(&mut self, run: ReleaseRun) {
+        self.runs.push(run);
+        if self.runs.len() > MAX_RUNS {
+            let excess = self.runs.len() - MAX_RUNS;
+            self.runs.drain(..excess);
+        }
+    }
+
+    pub fn estimate(&self, repo: &str) -> Option<StageEstimate> {
+        let mut secs: Vec<u64> = self
+            .runs
+            .iter()
+            .rev()
+            .flat_map(|run| run.stages.iter())
+            .filter(|stage| stage.repo == repo && stage.ok)
+            .map(|stage| stage.secs)
+            .take(ESTIMATE_SAMPLES)
+            .collect();
+        if secs.is_empty() {
+            return None;
+        }
+        secs.sort_unstable();
+
+        // Nearest-rank percentiles.
+        let p95_rank = (secs.len() * 95).div_ceil(100);
+        Some(StageEstimate {
+            median: Duration::from_secs(secs[secs.len() / 2]),
+            p95: Duration::from_secs(secs[p95_rank - 1]),
+            samples: secs.len(),
+        })
+    }
+
+    pub fn estimates(&self, repos: &[&str]) -> BTreeMap<String, StageEstimate> {
+        repos
+            .iter()
+            .filter_map(|repo| Some((repo.to_string(), self.estimate(repo)?)))
+            .collect()
+    }
+}
+
+/// Expected time for the whole release: the slowest `depends_on` chain of median stage
+/// times. `None` until every repo has history.
+pub fn overall_eta(
+    repos: &[&str],
+    definition: &ReleaseDefinition,
+    estimates: &BTreeMap<String, StageEstimate>,
+) -> Option<Duration> {
+    fn finish(
+        repo: &str,
+        definition: &ReleaseDefinition,
+        estimates: &BTreeMap<String, StageEstimate>,
+    ) -> Option<Duration> {
+        let mut start = Duration::ZERO;
+        for dependency in definition.depends_on(repo) {
+            start = start.max(finish(dependency, definition, estimates)?);
+        }
+        Some(start + estimates.get(repo)?.median)
+    }
+
+    repos
+        .iter()
+        .map(|repo| finish(repo, definition, estimates))
+        .try_fold(Duration::ZERO, |slowest, end| Some(slowest.max(end?)))
+}
+
+/// Appends the expected time left to a stage's progress, and flags a stage that has run
+/// longer than its historical p95.
+pub struct EtaReporter {
+    inner: DynReporter,
+    started: Instant,
+    estimate: StageEstimate,
+}
+
+impl EtaReporter {
+    /// Wraps `inner` when there is an estimate to report against.
+    pub fn wrap(
+        inner: &DynReporter,
+        estimate: Option<&StageEstimate>,
+        started: Instant,
+    ) -> DynReporter {
+        match estimate {
+            Some(estimate) => Arc::new(Self {
+                inner: inner.clone(),
+                started,
+                estimate: *estimate,
+            }),
+            None => inner.clone(),
+        }
+    }
+
+    fn annotate(&self, msg: String) -> String {
+        let elapsed = self.started.elapsed();
+        let note = if elapsed > self.estimate.p95 {
+            format!(
+                "WARNING: {} elapsed, over p95 of {}",
+                format_duration(elapsed),
+                format_duration(self.estimate.p95)
+            )
+        } else if elapsed < self.estimate.median {
+            format!("ETA {}", format_duration(self.estimate.median - elapsed))
+        } else {
+            format!(
+                "{} elapsed, usually {}",
+                format_duration(elapsed),
+                format_duration(self.estimate.median)
+            )
+        };
+        format!("{msg}  [{note}]")
+    }
+}
+
+impl Reporter for EtaReporter {
+    fn step(&self, title: String, body: String) {
+        self.inner.step(self.annotate(title), body);
+    }
+
+    fn update(&self, msg: String) {
+        self.inner.update(self.annotate(msg));
+    }
+
+    fn ok(&self, msg: String) {
+        self.inner.ok(msg);
+    }
+
+    fn error(&self, msg: String) {
+        self.inner.error(msg);
+    }
+
+    fn progress(&self, key: String, msg: String) {
+        self.inner.progress(key, msg);
+    }
+
+    fn confirm(&self, prompt: String) -> Result<bool> {
+        self.inner.confirm(prompt)
+    }
+}
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compact duration such as `45s`, `12m`, or `1h05m`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m", secs / 60),
+        _ => format!("{}h{:02}m", secs / 3_600, (secs % 3_600) / 60),
+    }
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM UTC`.
+fn format_utc(epoch: u64) -> String {
+    let (year, month, day) = civil_date(epoch);
+    let secs = epoch % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        secs / 3_600,
+        (secs % 3_600) / 60
+    )
+}
+
+pub fn run(args: ReleaseHistoryArgs, reporter: DynReporter) -> Result<()> {
+    let repos_root = match args.repos_root {
+        Some(p) => p,
+        None => default_repos_root()?,
+    };
+    let history = ReleaseHistory::load(&repos_root)?;
+
+    if history.runs.is_empty() {
+        reporter.step(
+            "Release history".to_string(),
+            format!(
+                "no releases recorded yet in {}",
+                ReleaseHistory::path(&repos_root).display()
+            ),
+        );
+        reporter.ok("OK".to_string());
+        return Ok(());
+    }
+
+    let width = ISO_CHAIN_REPOS
+        .iter()
+        .map(|repo| repo.len())
+        .max()
+        .unwrap_or(0);
+    let mut lines =
+        vec!["Stage durations (tag push to assets ready, recent successful runs):".to_string()];
+    for repo in ISO_CHAIN_REPOS {
+        lines.push(match history.estimate(repo) {
+            Some(estimate) => format!(
+                "  {repo:<width$}  median {:>6}  p95 {:>6}  ({} run(s))",
+                format_duration(estimate.median),
+                format_duration(estimate.p95),
+                estimate.samples
+            ),
+            None => format!("  {repo:<width$}  no data"),
+        });
+    }
+
+    let estimates = history.estimates(&ISO_CHAIN_REPOS);
+    if let Some(eta) = overall_eta(
+        &ISO_CHAIN_REPOS,
+        &ReleaseDefinition::load(args.release_definition.as_deref())?,
+        &estimates,
+    ) {
+        lines.push(format!(
+            "  expected total: {} (slowest dependency chain of medians)",
+            format_duration(eta)
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push("Recent runs:".to_string());
+    for run in history.runs.iter().rev().take(args.limit) {
+        let stages = run
+            .stages
+            .iter()
+            .map(|stage| {
+                format!(
+                    "{} {}{}",
+                    stage.repo,
+                    format_duration(Duration::from_secs(stage.secs)),
+                    if stage.ok { "" } else { " (failed)" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        lines.push(format!(
+            "  {:<14} {}  {:<6} {:>6}  {stages}",
+            run.tag,
+            format_utc(run.started_at),
+            if run.ok { "ok" } else { "FAILED" },
+            format_duration(Duration::from_secs(run.total_secs))
+        ));
+    }
+
+    reporter.step("Release history".to_string(), lines.join("\n"));
+    reporter.ok("OK".to_string());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(stages: &[(&str, u64, bool)]) -> ReleaseRun {
+        ReleaseRun {
+            tag: "v1.0.0".to_string(),
+            started_at: 0,
+            total_secs: 0,
+            ok: stages.iter().all(|(_, _, ok)| *ok),
+            stages: stages
+                .iter()
+                .map(|(repo, secs, ok)| StageTiming {
+                    repo: repo.to_string(),
+                    secs: *secs,
+                    ok: *ok,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn estimates_use_successful_stages() {
+        let mut history = ReleaseHistory::default();
+        for secs in 1..=20 {
+            history.record(run(&[("truthdb", secs * 60, true)]));
+        }
+        history.record(run(&[("truthdb", 10_000, false)]));
+
+        let estimate = history.estimate("truthdb").unwrap();
+        assert_eq!(estimate.samples, 20);
+        assert_eq!(estimate.median, Duration::from_secs(11 * 60));
+        assert_eq!(estimate.p95, Duration::from_secs(19 * 60));
+        assert!(history.estimate("installer").is_none());
+    }
+
+    #[test]
+    fn estimates_only_recent_stages() {
+        let mut history = ReleaseHistory::default();
+        for _ in 0..ESTIMATE_SAMPLES {
+            history.record(run(&[("truthdb", 600, true)]));
+        }
+        for _ in 0..ESTIMATE_SAMPLES {
+            history.record(run(&[("truthdb", 60, true)]));
+        }
+        assert_eq!(
+            history.estimate("truthdb").unwrap().p95,
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn record_keeps_the_latest_runs() {
+        let mut history = ReleaseHistory::default();
+        for secs in 0..(MAX_RUNS as u64 + 5) {
+            history.record(run(&[("truthdb", secs, true)]));
+        }
+        assert_eq!(history.runs.len(), MAX_RUNS);
+        assert_eq!(history.runs[0].stages[0].secs, 5);
+    }
+
+    #[test]
+    fn overall_eta_follows_slowest_chain() {
+        let definition: ReleaseDefinition = toml::from_str(
+            r#"
+            [repos.iso]
+            depends_on = ["kernel", "installer"]
+            "#,
+        )
+        .unwrap();
+        let estimate = |secs| StageEstimate {
+            median: Duration::from_secs(secs),
+            p95: Duration::from_secs(secs),
+            samples: 1,
+        };
+        let mut estimates = BTreeMap::from([
+            ("kernel".to_string(), estimate(600)),
+            ("installer".to_string(), estimate(300)),
+            ("iso".to_string(), estimate(120)),
+        ]);
+        let repos = ["kernel", "installer", "iso"];
+        assert_eq!(
+            overall_eta(&repos, &definition, &estimates),
+            Some(Duration::from_secs(720))
+        );
+
+        estimates.remove("installer");
+        assert_eq!(overall_eta(&repos, &definition, &estimates), None);
+    }
+
+    #[test]
+    fn formats_durations_and_dates() {
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(12 * 60 + 59)), "12m");
+        assert_eq!(format_duration(Duration::from_secs(3_900)), "1h05m");
+        assert_eq!(
+            format_utc(1_735_689_600 + 3_600 + 120),
+            "2025-01-01 01:02 UTC"
+        );
+    }
+}
@@ -1,26 +1,57 @@
-use crate::git::Repo;
+use crate::arch::Arch;
+use crate::cancel::{self, CancelToken, Cancelled};
+use crate::changelog::{self, Changes};
+use crate::git::{Repo, TagOptions};
 use crate::github::{
-    FALLBACK_GITHUB_TOKEN_ENV, GitHub, LEGACY_GITHUB_TOKEN_ENV, PRIMARY_GITHUB_TOKEN_ENV,
-    github_token,
+    CI_WORKFLOW_FILE, FALLBACK_GITHUB_TOKEN_ENV, GitHub, LEGACY_GITHUB_TOKEN_ENV,
+    PRIMARY_GITHUB_TOKEN_ENV, RELEASE_WORKFLOW_FILE, github_token,
 };
-use crate::reporter::DynReporter;
+use crate::iso_smoke::{self, SmokeOptions};
+use crate::release_definition::{self, ReleaseDefinition};
+use crate::release_history::{
+    self, EtaReporter, ReleaseHistory, ReleaseRun, StageEstimate, StageTiming,
+};
+use crate::release_stage;
+use crate::reporter::{DynReporter, KeyedReporter};
+use crate::version_bump::{self, BumpKind};
 use anyhow::{Context, Result, bail};
 use semver::Version;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 #[derive(Clone, Debug)]
 pub struct ReleaseIsoArgs {
-    pub version: String,
+    pub version: Option<String>,
+    pub bump: Option<BumpKind>,
+    pub yes: bool,
     pub repos_root: Option<PathBuf>,
+    pub release_definition: Option<PathBuf>,
+    /// Architectures whose assets every release must publish.
+    pub arches: Vec<Arch>,
     pub owner: String,
     pub dry_run: bool,
+    pub fix: bool,
+    pub allow_red_ci: bool,
+    pub download_dir: Option<PathBuf>,
+    pub smoke_test: bool,
+    /// `repo=<sha|branch>` overrides of the commit to tag; other repos tag HEAD.
+    pub at: Vec<String>,
     pub resume: bool,
+    pub sign: bool,
+    pub edit_tag_message: bool,
     pub poll_interval: Duration,
     pub timeout: Duration,
+    pub cancel: CancelToken,
 }
 
-fn parse_and_normalize_version(input: &str) -> Result<(String, String)> {
+/// Repos that make up an installer ISO release. `depends_on` in the release definition
+/// decides which of them are released concurrently.
+pub const ISO_CHAIN_REPOS: [&str; 4] =
+    ["installer-kernel", "installer", "truthdb", "installer-iso"];
+
+pub fn parse_and_normalize_version(input: &str) -> Result<(String, String)> {
     // Accept inputs like:
     // - 1.2.3
     // - v1.2.3
@@ -48,7 +79,7 @@ fn parse_and_normalize_version(input: &str) -> Result<(String, String)> {
     Ok((tag, version))
 }
 
-fn default_repos_root() -> Result<PathBuf> {
+pub fn default_repos_root() -> Result<PathBuf> {
     let cwd = std::env::current_dir().context("failed to read current directory")?;
 
     if looks_like_repos_root(&cwd) {
@@ -71,50 +102,546 @@ fn default_repos_root() -> Result<PathBuf> {
 }
 
 fn looks_like_repos_root(dir: &Path) -> bool {
-    ["truthdb", "installer", "installer-kernel", "installer-iso"]
+    ISO_CHAIN_REPOS.iter().all(|name| dir.join(name).is_dir())
+}
+
+/// The x86_64 ISO keeps its original unsuffixed name; other arches add `-<arch>`.
+fn iso_asset_name(version_without_v: &str, arch: Arch) -> String {
+    match arch {
+        Arch::X86_64 => format!("truthdb-installer-v{version_without_v}.iso"),
+        arch => format!("truthdb-installer-v{version_without_v}-{arch}.iso"),
+    }
+}
+
+pub fn expected_assets(repo: &str, version_without_v: &str, arches: &[Arch]) -> Vec<String> {
+    arches
         .iter()
-        .all(|name| dir.join(name).is_dir())
+        .flat_map(|&arch| match repo {
+            "installer-kernel" => vec![
+                arch.efi_boot_name().to_string(),
+                format!("{}.sha256", arch.efi_boot_name()),
+            ],
+            "installer" => vec![
+                format!("truthdb-installer-v{version_without_v}-{arch}-linux-musl.tar.gz"),
+                format!("truthdb-installer-v{version_without_v}-{arch}-linux-musl.sha256"),
+            ],
+            "truthdb" => vec![
+                format!("truthdb-v{version_without_v}-{arch}-linux-gnu.tar.gz"),
+                format!("truthdb-v{version_without_v}-{arch}-linux-gnu.sha256"),
+            ],
+            "installer-iso" => vec![
+                iso_asset_name(version_without_v, arch),
+                format!("{}.sha256", iso_asset_name(version_without_v, arch)),
+            ],
+            _ => Vec::new(),
+        })
+        .collect()
 }
 
-fn expected_assets(repo: &str, version_without_v: &str) -> Vec<String> {
-    match repo {
-        "installer-kernel" => vec!["BOOTX64.EFI".to_string(), "BOOTX64.EFI.sha256".to_string()],
-        "installer" => vec![
-            format!(
-                "truthdb-installer-v{}-x86_64-linux-musl.tar.gz",
-                version_without_v
-            ),
-            format!(
-                "truthdb-installer-v{}-x86_64-linux-musl.sha256",
-                version_without_v
-            ),
-        ],
-        "truthdb" => vec![
-            format!("truthdb-v{}-x86_64-linux-gnu.tar.gz", version_without_v),
-            format!("truthdb-v{}-x86_64-linux-gnu.sha256", version_without_v),
-        ],
-        "installer-iso" => vec![
-            format!("truthdb-installer-v{}.iso", version_without_v),
-            format!("truthdb-installer-v{}.iso.sha256", version_without_v),
-        ],
-        _ => Vec::new(),
+/// Finds the highest release across the ISO repos, bumps it, and checks the result is unused.
+fn resolve_bumped_version(
+    repos: &[Repo],
+    gh: Option<&GitHub>,
+    kind: BumpKind,
+    reporter: &DynReporter,
+) -> Result<(String, String)> {
+    reporter.step(
+        "Version".to_string(),
+        format!(
+            "Finding highest release for --bump {}",
+            format!("{kind:?}").to_lowercase()
+        ),
+    );
+    if gh.is_none() {
+        reporter.update("no GitHub token; using local tags only".to_string());
+    }
+
+    let mut highest: Option<(Version, String)> = None;
+    let mut consider = |version: Version, source: String| {
+        if highest.as_ref().is_none_or(|(best, _)| version > *best) {
+            highest = Some((version, source));
+        }
+    };
+
+    for repo in repos {
+        if !repo.dir.is_dir() {
+            bail!("repo directory not found: {}", repo.dir.display());
+        }
+        // Tags from the wrong remote would pick the wrong next version, so check it first.
+        reporter.update(format!("[{}] verifying origin remote…", repo.name));
+        repo.ensure_origin_matches_expected()?;
+        reporter.update(format!("[{}] fetching tags…", repo.name));
+        repo.fetch_origin()?;
+        for version in repo.release_versions()? {
+            consider(version, format!("{} tag", repo.name));
+        }
+
+        if let Some(gh) = gh
+            && let Some(tag) = gh.get_latest_release_tag(&repo.name)?
+            && let Some(version) = tag.strip_prefix('v').and_then(|v| Version::parse(v).ok())
+        {
+            consider(version, format!("{} latest GitHub release", repo.name));
+        }
+    }
+
+    let Some((current, source)) = highest else {
+        bail!(
+            "no v{{semver}} release tags found in any ISO repo; pass --version for the first release"
+        );
+    };
+    let next = version_bump::next_version(&current, kind);
+    let tag = format!("v{next}");
+
+    reporter.update(format!("Checking {tag} is unused…"));
+    for repo in repos {
+        if repo.local_tag_commit(&tag)?.is_some() || repo.remote_tag_commit(&tag)?.is_some() {
+            bail!("bumped version {tag} is already tagged in {}", repo.name);
+        }
+        if let Some(gh) = gh
+            && gh.get_release_by_tag(&repo.name, &tag)?.is_some()
+        {
+            bail!(
+                "bumped version {tag} already has a GitHub release in {}",
+                repo.name
+            );
+        }
+    }
+
+    reporter.update(format!(
+        "highest existing release: v{current} ({source})\nnext: {tag}"
+    ));
+    Ok((tag, next.to_string()))
+}
+
+/// The commit a repo's release tag goes on.
+#[derive(Clone, Debug)]
+struct ReleaseTarget {
+    commit: String,
+    /// Origin branch whose tip is `commit`, when known; CI is then checked on that branch.
+    branch: Option<String>,
+    /// Whether `commit` is the checked-out HEAD rather than an `--at` override.
+    is_head: bool,
+}
+
+impl ReleaseTarget {
+    fn label(&self) -> String {
+        if self.is_head {
+            format!("HEAD {}", short_sha(&self.commit))
+        } else {
+            short_sha(&self.commit).to_string()
+        }
     }
 }
 
+/// Parses `--at repo=<sha|branch>` values into a map keyed by repo.
+fn parse_release_at(values: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut at = BTreeMap::new();
+    for value in values {
+        let Some((repo, rev)) = value.split_once('=') else {
+            bail!("invalid --at '{value}': expected <repo>=<commit or branch>");
+        };
+        let (repo, rev) = (repo.trim(), rev.trim());
+        if !ISO_CHAIN_REPOS.contains(&repo) {
+            bail!(
+                "invalid --at '{value}': {repo} is not an ISO repo ({})",
+                ISO_CHAIN_REPOS.join(", ")
+            );
+        }
+        if rev.is_empty() {
+            bail!("invalid --at '{value}': missing commit or branch");
+        }
+        if at.insert(repo.to_string(), rev.to_string()).is_some() {
+            bail!("--at given more than once for {repo}");
+        }
+    }
+    Ok(at)
+}
+
+/// Resolves an `--at` revision to a commit that is already on origin.
+///
+/// A name matching a branch on origin means that branch's tip; anything else must resolve
+/// to a commit contained in some origin branch.
+fn resolve_release_at(repo: &Repo, rev: &str, reporter: &DynReporter) -> Result<ReleaseTarget> {
+    if let Some(commit) = repo.remote_branch_commit(rev)? {
+        reporter.update(format!("tagging origin/{rev} at {}", short_sha(&commit)));
+        return Ok(ReleaseTarget {
+            commit,
+            branch: Some(rev.to_string()),
+            is_head: false,
+        });
+    }
+
+    let commit = repo.resolve_commit(rev)?.with_context(|| {
+        format!(
+            "{}: --at {rev} is neither a branch on origin nor a known commit",
+            repo.name
+        )
+    })?;
+    let branches = repo.remote_branches_containing(&commit)?;
+    if branches.is_empty() {
+        bail!(
+            "{}: commit {} is not on any origin branch; push it before releasing from it",
+            repo.name,
+            short_sha(&commit)
+        );
+    }
+    reporter.update(format!(
+        "tagging {} (on origin/{})",
+        short_sha(&commit),
+        branches.join(", origin/")
+    ));
+    Ok(ReleaseTarget {
+        commit,
+        branch: None,
+        is_head: false,
+    })
+}
+
+/// Why the latest CI run on `branch` doesn't vouch for local HEAD, if it doesn't.
+fn ci_problem(gh: &GitHub, repo: &Repo, target: &ReleaseTarget) -> Result<Option<String>> {
+    let label = target.label();
+    let run = match &target.branch {
+        Some(branch) => {
+            let Some(run) = gh.get_latest_workflow_run(&repo.name, CI_WORKFLOW_FILE, branch)?
+            else {
+                return Ok(Some(format!("no {CI_WORKFLOW_FILE} run found on {branch}")));
+            };
+            if run.head_sha != target.commit {
+                return Ok(Some(format!(
+                    "latest {CI_WORKFLOW_FILE} run on {branch} is for {}, not {label}",
+                    short_sha(&run.head_sha),
+                )));
+            }
+            run
+        }
+        None => {
+            let Some(run) =
+                gh.find_workflow_run_for_commit(&repo.name, CI_WORKFLOW_FILE, &target.commit)?
+            else {
+                return Ok(Some(format!("no {CI_WORKFLOW_FILE} run found for {label}")));
+            };
+            run
+        }
+    };
+
+    if run.status != "completed" {
+        return Ok(Some(format!(
+            "{CI_WORKFLOW_FILE} for {label} is still {} ({})",
+            run.status, run.html_url
+        )));
+    }
+    if run.conclusion.as_deref() != Some("success") {
+        return Ok(Some(format!(
+            "{CI_WORKFLOW_FILE} for {label} concluded {} ({})",
+            run.conclusion.as_deref().unwrap_or("without a result"),
+            run.html_url
+        )));
+    }
+    Ok(None)
+}
+
+/// Comma-separated arch names, e.g. `x86_64, aarch64`.
+pub fn arches_label(arches: &[Arch]) -> String {
+    arches
+        .iter()
+        .map(|arch| arch.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(12)]
+}
+
+/// Plan entry for one repo: what gets tagged, what's in it, and which assets to expect.
+///
+/// `target` is `None` for repos already tagged on origin (only their assets are awaited).
+fn plan_lines(
+    repo: &Repo,
+    target: Option<&ReleaseTarget>,
+    tag: &str,
+    version: &Version,
+    depends_on: &[String],
+    estimate: Option<&StageEstimate>,
+    expected: &[String],
+) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    match target {
+        Some(target) => {
+            let branch = match (&target.branch, target.is_head) {
+                (Some(branch), true) => branch.clone(),
+                (Some(branch), false) => format!("origin/{branch}"),
+                (None, _) => "commit".to_string(),
+            };
+            let changes = Changes::collect(repo, version, &target.commit)?;
+            lines.push(format!(
+                "{}: {branch} @ {} {}",
+                repo.name,
+                short_sha(&target.commit),
+                repo.commit_subject(&target.commit)?
+            ));
+            lines.push(format!(
+                "  tag {tag}, {} commit(s) since {}",
+                changes.commits.len(),
+                changes
+                    .previous_tag
+                    .as_deref()
+                    .unwrap_or("the first commit")
+            ));
+        }
+        None => lines.push(format!("{}: {tag} already on origin", repo.name)),
+    }
+    if !depends_on.is_empty() {
+        lines.push(format!("  after: {}", depends_on.join(", ")));
+    }
+    if !expected.is_empty() {
+        lines.push(format!("  assets: {}", expected.join(", ")));
+    }
+    if let Some(estimate) = estimate {
+        lines.push(format!(
+            "  usually {} from push to assets (p95 {}, {} run(s))",
+            release_history::format_duration(estimate.median),
+            release_history::format_duration(estimate.p95),
+            estimate.samples
+        ));
+    }
+    Ok(lines)
+}
+
+/// Polling cadence and per-repo limit while a tag's release is being built.
+#[derive(Clone, Copy, Debug)]
+pub struct WaitOptions {
+    pub poll_interval: Duration,
+    pub timeout: Duration,
+}
+
+/// Follows the pushed tag's release workflow, then waits for `expected` assets.
+pub fn wait_for_release(
+    gh: &GitHub,
+    repo: &Repo,
+    tag: &str,
+    expected: &[String],
+    wait: &WaitOptions,
+    reporter: &DynReporter,
+) -> Result<()> {
+    reporter.step(
+        format!("Watching {RELEASE_WORKFLOW_FILE} [{}]", repo.name),
+        format!("tag={tag}"),
+    );
+    // One deadline covers the workflow and the assets, so --timeout bounds the whole wait.
+    let deadline = Instant::now() + wait.timeout;
+    let tag_commit = repo
+        .remote_tag_commit(tag)?
+        .with_context(|| format!("{} tag {tag} is missing on origin", repo.name))?;
+    gh.wait_for_release_workflow(
+        &repo.name,
+        tag,
+        &tag_commit,
+        wait.poll_interval,
+        wait.timeout,
+        reporter.as_ref(),
+    )
+    .with_context(|| format!("watching {} {RELEASE_WORKFLOW_FILE}", repo.name))?;
+
+    reporter.step(
+        format!("Waiting for assets [{}]", repo.name),
+        format!("expected={:?}", expected),
+    );
+    gh.wait_for_release_assets(
+        &repo.name,
+        tag,
+        expected,
+        wait.poll_interval,
+        deadline.saturating_duration_since(Instant::now()),
+        reporter.as_ref(),
+    )
+    .with_context(|| format!("waiting for {} assets", repo.name))
+}
+
+/// One line per repo: its name and the repos it waits for.
+pub fn stage_summary(repos: &[Repo], definition: &ReleaseDefinition) -> String {
+    repos
+        .iter()
+        .map(|repo| match definition.depends_on(&repo.name) {
+            [] => repo.name.clone(),
+            dependencies => format!("{} (after {})", repo.name, dependencies.join(", ")),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Where a repo's stage is while the release runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StageState {
+    Waiting,
+    Running,
+    Done,
+    Failed,
+    Skipped,
+}
+
+/// Runs `stage` for every repo on its own thread, starting each one once all of its
+/// `depends_on` are done.
+///
+/// A failed stage skips everything downstream of it; unrelated stages still run to the end.
+pub fn run_stages<F>(
+    repos: &[Repo],
+    definition: &ReleaseDefinition,
+    reporter: &DynReporter,
+    stage: F,
+) -> Result<()>
+where
+    F: Fn(&Repo, &DynReporter) -> Result<()> + Sync,
+{
+    let states: Mutex<BTreeMap<&str, StageState>> = Mutex::new(
+        repos
+            .iter()
+            .map(|repo| (repo.name.as_str(), StageState::Waiting))
+            .collect(),
+    );
+    let changed = Condvar::new();
+    let failures: Mutex<Vec<(String, anyhow::Error)>> = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for repo in repos {
+            let (states, changed, failures, stage) = (&states, &changed, &failures, &stage);
+            scope.spawn(move || {
+                let keyed: DynReporter =
+                    Arc::new(KeyedReporter::new(reporter.clone(), repo.name.clone()));
+                let dependencies = definition.depends_on(&repo.name);
+                if !dependencies.is_empty() {
+                    keyed.update(format!("waiting for {}", dependencies.join(", ")));
+                }
+
+                let mut guard = states.lock().expect("stage states poisoned");
+                let blocked = loop {
+                    if let Some(dependency) = dependencies.iter().find(|dependency| {
+                        matches!(
+                            guard[dependency.as_str()],
+                            StageState::Failed | StageState::Skipped
+                        )
+                    }) {
+                        break Some(dependency);
+                    }
+                    if dependencies
+                        .iter()
+                        .all(|dependency| guard[dependency.as_str()] == StageState::Done)
+                    {
+                        break None;
+                    }
+                    guard = changed.wait(guard).expect("stage states poisoned");
+                };
+
+                if let Some(dependency) = blocked {
+                    guard.insert(repo.name.as_str(), StageState::Skipped);
+                    drop(guard);
+                    changed.notify_all();
+                    keyed.update(format!("skipped: {dependency} did not finish"));
+                    return;
+                }
+                guard.insert(repo.name.as_str(), StageState::Running);
+                drop(guard);
+
+                let state = match stage(repo, &keyed) {
+                    Ok(()) => {
+                        keyed.update("done".to_string());
+                        StageState::Done
+                    }
+                    Err(err) => {
+                        keyed.update("FAILED".to_string());
+                        failures
+                            .lock()
+                            .expect("stage failures poisoned")
+                            .push((repo.name.clone(), err));
+                        StageState::Failed
+                    }
+                };
+                states
+                    .lock()
+                    .expect("stage states poisoned")
+                    .insert(repo.name.as_str(), state);
+                changed.notify_all();
+            });
+        }
+    });
+
+    let failures = failures.into_inner().expect("stage failures poisoned");
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    let cancelled: Vec<String> = failures
+        .iter()
+        .filter_map(|(repo, err)| Some(format!("{} [{repo}]", cancel::cancelled(err)?.stage)))
+        .collect();
+    if cancelled.len() == failures.len() {
+        return Err(Cancelled {
+            stage: cancelled.join(", "),
+        }
+        .into());
+    }
+
+    let mut details = failures
+        .iter()
+        .map(|(repo, err)| format!("[{repo}] {err:#}"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let skipped: Vec<&str> = states
+        .into_inner()
+        .expect("stage states poisoned")
+        .into_iter()
+        .filter(|(_, state)| *state == StageState::Skipped)
+        .map(|(repo, _)| repo)
+        .collect();
+    if !skipped.is_empty() {
+        details.push_str(&format!("\n\nnot started: {}", skipped.join(", ")));
+    }
+    bail!("release failed in {} repo(s):\n{details}", failures.len())
+}
+
 pub fn run(args: ReleaseIsoArgs, reporter: DynReporter) -> Result<()> {
-    let (tag, version_without_v) = parse_and_normalize_version(&args.version)?;
+    // --bump can't be combined with --resume, so the hint names the version it resolved to.
+    let mut resolved_tag = None;
+    release(args, reporter, &mut resolved_tag).map_err(|err| {
+        match (cancel::cancelled(&err), resolved_tag) {
+            (Some(cancelled), Some(tag)) => {
+                anyhow::anyhow!("{cancelled}; resume with --version {tag} --resume")
+            }
+            _ => err,
+        }
+    })
+}
 
+fn release(
+    mut args: ReleaseIsoArgs,
+    reporter: DynReporter,
+    resolved_tag: &mut Option<String>,
+) -> Result<()> {
+    args.arches.sort();
+    args.arches.dedup();
+    // QEMU/OVMF defaults are x86_64-only; fail before planning rather than skip at the end.
+    if args.smoke_test && !args.arches.contains(&Arch::X86_64) {
+        bail!(
+            "--smoke-test only boots the x86_64 ISO, but --arch is {}; add --arch x86_64 or drop --smoke-test",
+            arches_label(&args.arches)
+        );
+    }
     reporter.step(
         "Initialize".to_string(),
         format!(
-            "version={} (tag={})\nmode={}{}",
-            version_without_v,
-            tag,
+            "mode={}{}{}\narch={}",
             if args.dry_run { "dry-run" } else { "live" },
-            if args.resume { ", resume" } else { "" }
+            if args.resume { ", resume" } else { "" },
+            if args.sign { ", signed tags" } else { "" },
+            arches_label(&args.arches)
         ),
     );
 
+    let wait = WaitOptions {
+        poll_interval: args.poll_interval,
+        timeout: args.timeout,
+    };
+
     let repos_root = match args.repos_root {
         Some(p) => p,
         None => default_repos_root()?,
@@ -122,24 +649,54 @@ pub fn run(args: ReleaseIsoArgs, reporter: DynReporter) -> Result<()> {
 
     reporter.update(format!("repos_root={}", repos_root.display()));
 
-    let repos_in_order = ["installer-kernel", "installer", "truthdb", "installer-iso"];
+    let definition = ReleaseDefinition::load(args.release_definition.as_deref())?;
+    definition.check_dependencies(&ISO_CHAIN_REPOS)?;
+    let mut history = ReleaseHistory::load(&repos_root)?;
+    let estimates = history.estimates(&ISO_CHAIN_REPOS);
 
-    let repos: Vec<Repo> = repos_in_order
+    let repos: Vec<Repo> = ISO_CHAIN_REPOS
         .iter()
         .map(|name| Repo::new(&args.owner, *name, repos_root.join(name)))
         .collect();
 
+    let token = github_token();
+
+    if !args.dry_run && token.is_empty() {
+        bail!(
+            "missing {}, {}, or {}. This is required to poll release assets after tagging.",
+            PRIMARY_GITHUB_TOKEN_ENV,
+            FALLBACK_GITHUB_TOKEN_ENV,
+            LEGACY_GITHUB_TOKEN_ENV
+        );
+    }
+
+    let gh = if token.is_empty() {
+        None
+    } else {
+        Some(GitHub::new(args.owner.clone(), token)?.with_cancel(args.cancel.clone()))
+    };
+
+    let (tag, version_without_v) = match (&args.version, args.bump) {
+        (Some(version), _) => parse_and_normalize_version(version)?,
+        (None, Some(kind)) => resolve_bumped_version(&repos, gh.as_ref(), kind, &reporter)?,
+        (None, None) => bail!("pass --version or --bump"),
+    };
+    let semver = Version::parse(&version_without_v)?;
+    reporter.update(format!("version={version_without_v} (tag={tag})"));
+    *resolved_tag = Some(tag.clone());
+
     // Preflight: do all safety checks up-front before we mutate anything.
     // In --resume mode, we only require strict "A" checks on repos that are not
     // already tagged on origin.
-    let mut remote_tagged: std::collections::BTreeMap<String, bool> =
-        std::collections::BTreeMap::new();
+    let release_at = parse_release_at(&args.at)?;
+    let mut remote_tagged: BTreeMap<String, bool> = BTreeMap::new();
+    let mut targets: BTreeMap<String, ReleaseTarget> = BTreeMap::new();
+    let mut version_mismatches = Vec::new();
 
     for repo in &repos {
-        reporter.step(
-            format!("Preflight [{}]", repo.name),
-            format!("Checking repo at {}", repo.dir.display()),
-        );
+        let stage = format!("Preflight [{}]", repo.name);
+        args.cancel.check(&stage)?;
+        reporter.step(stage, format!("Checking repo at {}", repo.dir.display()));
 
         if !repo.dir.is_dir() {
             bail!("repo directory not found: {}", repo.dir.display());
@@ -167,106 +724,386 @@ pub fn run(args: ReleaseIsoArgs, reporter: DynReporter) -> Result<()> {
             );
         }
 
-        // Not yet tagged on origin: enforce strict "A" safety checks.
-        reporter.update("Ensuring worktree clean…".to_string());
-        repo.ensure_worktree_clean()?;
+        // Not yet tagged on origin: enforce strict "A" safety checks. An --at commit is
+        // tagged without touching the checkout, so only its presence on origin matters.
+        let target = match release_at.get(&repo.name) {
+            Some(rev) => {
+                reporter.update(format!("Resolving --at {rev}…"));
+                resolve_release_at(repo, rev, &reporter)?
+            }
+            None => {
+                reporter.update("Ensuring worktree clean…".to_string());
+                repo.ensure_worktree_clean()?;
 
-        reporter.update("Ensuring branch is synced with origin…".to_string());
-        let _branch = repo.ensure_on_branch_and_synced_to_origin()?;
+                reporter.update("Ensuring branch is synced with origin…".to_string());
+                let branch = repo.ensure_on_branch_and_synced_to_origin()?;
+                ReleaseTarget {
+                    commit: repo.head_commit()?,
+                    branch: Some(branch),
+                    is_head: true,
+                }
+            }
+        };
 
-        // In resume mode, allow a pre-existing local tag only if it points at HEAD.
-        if args.resume {
-            if let Some(local_tag_commit) = repo.local_tag_commit(&tag)? {
-                let head_commit = repo.head_commit()?;
-
-                if local_tag_commit != head_commit {
-                    bail!(
-                        "{} already has local tag {tag}, but it does not point at HEAD (tag={}, head={}). Refusing to push; delete/fix the local tag or choose a new version.",
-                        repo.dir.display(),
-                        local_tag_commit,
-                        head_commit
-                    );
+        match &gh {
+            Some(gh) => {
+                reporter.update(format!(
+                    "Checking {CI_WORKFLOW_FILE} for {}…",
+                    target.label()
+                ));
+                if let Some(problem) = ci_problem(gh, repo, &target)? {
+                    if !args.allow_red_ci {
+                        bail!(
+                            "{}: {problem}. Re-run with --allow-red-ci to tag anyway.",
+                            repo.name
+                        );
+                    }
+                    reporter.update(format!("warning: {problem} (--allow-red-ci)"));
                 }
             }
+            None => reporter.update("no GitHub token; skipping CI check".to_string()),
+        }
+
+        let version_files = definition.version_files(&repo.name);
+        if !version_files.is_empty() {
+            reporter.update("Checking version files…".to_string());
+            let mismatches =
+                release_definition::check_version_files(version_files, &semver, |path| {
+                    if target.is_head {
+                        let path = repo.dir.join(path);
+                        std::fs::read_to_string(&path)
+                            .with_context(|| format!("failed to read {}", path.display()))
+                    } else {
+                        repo.read_file_at(&target.commit, path)
+                    }
+                })?;
+            if !mismatches.is_empty() {
+                version_mismatches.push((repo, mismatches, target.is_head));
+            }
+        }
+
+        if args.sign {
+            reporter.update("Checking tag signing key…".to_string());
+            let key = repo.ensure_signing_key_configured()?;
+            reporter.update(format!("signing with {key}"));
+        }
+
+        // In resume mode, allow a pre-existing local tag only if it points at the release commit.
+        if args.resume {
+            if let Some(local_tag_commit) = repo.local_tag_commit(&tag)?
+                && local_tag_commit != target.commit
+            {
+                bail!(
+                    "{} already has local tag {tag}, but it does not point at {} (tag={}). Refusing to push; delete/fix the local tag or choose a new version.",
+                    repo.dir.display(),
+                    target.label(),
+                    local_tag_commit
+                );
+            }
         } else {
             reporter.update("Ensuring local/remote tag absent…".to_string());
             repo.ensure_tag_absent_local_and_remote(&tag)?;
         }
+
+        targets.insert(repo.name.clone(), target);
     }
 
-    let token = github_token();
+    if !version_mismatches.is_empty() {
+        let summary = version_mismatches
+            .iter()
+            .flat_map(|(repo, mismatches, _)| {
+                mismatches
+                    .iter()
+                    .map(move |m| format!("  {}: {}", repo.name, m.describe()))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
 
-    if !args.dry_run && token.is_empty() {
-        bail!(
-            "missing {}, {}, or {}. This is required to poll release assets after tagging.",
-            PRIMARY_GITHUB_TOKEN_ENV,
-            FALLBACK_GITHUB_TOKEN_ENV,
-            LEGACY_GITHUB_TOKEN_ENV
+        if !args.fix {
+            bail!(
+                "version files disagree with {tag}:\n{summary}\n\nRe-run with --fix to commit the version bumps."
+            );
+        }
+
+        reporter.step(
+            "Version bump".to_string(),
+            format!("version files disagree with {tag}:\n{summary}"),
         );
-    }
 
-    let gh = if args.dry_run || token.is_empty() {
-        None
-    } else {
-        Some(GitHub::new(args.owner.clone(), token)?)
-    };
+        if let Some((repo, _, _)) = version_mismatches.iter().find(|(_, _, is_head)| !is_head) {
+            bail!(
+                "--fix can't bump {} because it is released from --at; commit the version bump on that branch first",
+                repo.name
+            );
+        }
 
+        if args.dry_run {
+            reporter.update(format!(
+                "(dry-run) would rewrite them to {version_without_v} and commit in each repo"
+            ));
+        } else {
+            let message = format!("chore: bump version to {version_without_v}");
+            let mut committed = Vec::new();
+            for (repo, mismatches, _) in &version_mismatches {
+                let mut changed = Vec::new();
+                for mismatch in mismatches {
+                    changed.extend(mismatch.file.write(&repo.dir, &version_without_v)?);
+                }
+                let commit = repo.commit_paths(&changed, &message)?;
+                reporter.update(format!(
+                    "[{}] committed {} ({})",
+                    repo.name,
+                    short_sha(&commit),
+                    message
+                ));
+                committed.push(repo.name.as_str());
+            }
+
+            reporter.ok(format!(
+                "Committed version bumps in {}. Review and push them, then re-run release-iso for {tag}.",
+                committed.join(", ")
+            ));
+            return Ok(());
+        }
+    }
+
+    let mut plan = Vec::new();
     for repo in &repos {
+        plan.extend(plan_lines(
+            repo,
+            targets.get(&repo.name),
+            &tag,
+            &semver,
+            definition.depends_on(&repo.name),
+            estimates.get(&repo.name),
+            &expected_assets(&repo.name, &version_without_v, &args.arches),
+        )?);
+    }
+    reporter.step(format!("Plan for {tag}"), plan.join("\n"));
+
+    if !args.dry_run && !args.yes && !reporter.confirm(format!("Create and push {tag}?"))? {
+        bail!("release cancelled; nothing was tagged");
+    }
+
+    // Tag messages are prepared up front: --edit-tag-message opens an editor per repo,
+    // which can't share the terminal with the concurrent stages below.
+    let mut messages: BTreeMap<String, String> = BTreeMap::new();
+    if !args.dry_run && !targets.is_empty() {
+        reporter.step("Tag messages".to_string(), format!("tag={tag}"));
+        for repo in &repos {
+            args.cancel.check("Tag messages")?;
+            let Some(target) = targets.get(&repo.name) else {
+                continue;
+            };
+            if repo.local_tag_commit(&tag)?.is_some() {
+                continue;
+            }
+            reporter.update(format!("[{}] Generating changelog…", repo.name));
+            let changes = Changes::collect(repo, &semver, &target.commit)?;
+            let mut message = changelog::render_tag_message(&tag, &changes);
+            if args.edit_tag_message {
+                message = changelog::edit_message(&repo.name, &message)?;
+            }
+            messages.insert(repo.name.clone(), message);
+        }
+    }
+
+    let mut summary = stage_summary(&repos, &definition);
+    if let Some(eta) = release_history::overall_eta(&ISO_CHAIN_REPOS, &definition, &estimates) {
+        summary.push_str(&format!(
+            "\n\nETA {} from previous releases",
+            release_history::format_duration(eta)
+        ));
+    }
+    reporter.step(format!("Releasing {tag}"), summary);
+    let started_at = release_history::unix_now();
+    let started = Instant::now();
+    let timings: Mutex<Vec<StageTiming>> = Mutex::new(Vec::new());
+    // Tags are created and pushed one repo at a time, since signing or pushing may prompt.
+    let tagging = Mutex::new(());
+    let check_dir =
+        std::env::temp_dir().join(format!("orchestrator-assets-{}", std::process::id()));
+    let result = run_stages(&repos, &definition, &reporter, |repo, reporter| {
+        let mut pushed_at = None;
         let already_remote_tagged = *remote_tagged.get(&repo.name).unwrap_or(&false);
-        reporter.step(format!("Tagging [{}]", repo.name), format!("tag={}", tag));
+        args.cancel.check("Tagging")?;
+        reporter.step("Tagging".to_string(), format!("tag={tag}"));
 
         if args.dry_run {
             if already_remote_tagged {
+                reporter.update("(dry-run) tag already on origin; would skip tagging".to_string());
+            } else if let Some(target) = targets.get(&repo.name) {
+                let changes = Changes::collect(repo, &semver, &target.commit)?;
                 reporter.update(format!(
-                    "[{}] (dry-run) tag already on origin; would skip tagging",
-                    repo.name
-                ));
-            } else {
-                reporter.update(format!(
-                    "[{}] (dry-run) would create annotated tag and push",
-                    repo.name
+                    "(dry-run) would create {} tag at {} with {} commit(s) since {} and push",
+                    if args.sign { "signed" } else { "annotated" },
+                    target.label(),
+                    changes.commits.len(),
+                    changes
+                        .previous_tag
+                        .as_deref()
+                        .unwrap_or("the first commit")
                 ));
             }
         } else if already_remote_tagged {
-            reporter.update(format!(
-                "[{}] tag already exists on origin; skipping create/push",
-                repo.name
-            ));
+            reporter.update("tag already exists on origin; skipping create/push".to_string());
         } else {
+            let target = targets
+                .get(&repo.name)
+                .context("release target missing after preflight")?;
+            let _tagging = tagging.lock().expect("tagging lock poisoned");
+
             // Create tag if it doesn't already exist locally; in --resume mode it may.
             if repo.local_tag_commit(&tag)?.is_none() {
-                reporter.update("Creating annotated tag…".to_string());
-                repo.create_annotated_tag(&tag)?;
+                let message = messages
+                    .get(&repo.name)
+                    .context("tag message missing after preflight")?;
+                reporter.update(if args.sign {
+                    "Creating signed tag…".to_string()
+                } else {
+                    "Creating annotated tag…".to_string()
+                });
+                let tag_options = TagOptions {
+                    sign: args.sign,
+                    message: Some(message.clone()),
+                    target: Some(target.commit.clone()),
+                };
+                repo.create_annotated_tag(&tag, &tag_options)?;
+            }
+
+            if args.sign {
+                reporter.update("Verifying tag signature…".to_string());
+                repo.verify_tag_signature(&tag)?;
             }
 
+            args.cancel.check("Pushing tag")?;
             reporter.update("Pushing tag to origin…".to_string());
             repo.push_tag(&tag)?;
+            pushed_at = Some(Instant::now());
         }
 
-        let expected = expected_assets(&repo.name, &version_without_v);
+        let expected = expected_assets(&repo.name, &version_without_v, &args.arches);
         if expected.is_empty() {
-            continue;
+            return Ok(());
+        }
+
+        let checked: Vec<String> = expected
+            .iter()
+            .flat_map(|name| {
+                definition
+                    .checks_for(name)
+                    .into_iter()
+                    .map(move |check| format!("{name} ({})", check.kind))
+            })
+            .collect();
+
+        if args.dry_run {
+            reporter.update(format!(
+                "(dry-run) would watch {RELEASE_WORKFLOW_FILE} and wait for assets: {:?}",
+                expected
+            ));
+            if !checked.is_empty() {
+                reporter.update(format!("(dry-run) would check {}", checked.join(", ")));
+            }
+        } else if let Some(ref gh) = gh {
+            match pushed_at {
+                Some(pushed_at) => {
+                    let reporter =
+                        EtaReporter::wrap(reporter, estimates.get(&repo.name), pushed_at);
+                    let result = wait_for_release(gh, repo, &tag, &expected, &wait, &reporter);
+                    timings
+                        .lock()
+                        .expect("stage timings poisoned")
+                        .push(StageTiming {
+                            repo: repo.name.clone(),
+                            secs: pushed_at.elapsed().as_secs(),
+                            ok: result.is_ok(),
+                        });
+                    result?;
+                }
+                // Pushed by an earlier run, so there's no push time to measure from.
+                None => wait_for_release(gh, repo, &tag, &expected, &wait, reporter)?,
+            }
+
+            if !checked.is_empty() {
+                args.cancel.check("Checking assets")?;
+                reporter.step("Checking assets".to_string(), checked.join("\n"));
+                // With --download-dir the checked files land where staging will look for them.
+                let dir = match &args.download_dir {
+                    Some(dir) => dir.join(&tag).join(&repo.name),
+                    None => check_dir.join(&repo.name),
+                };
+                release_stage::check_repo_assets(
+                    gh,
+                    &repo.name,
+                    &tag,
+                    &dir,
+                    &definition.asset_checks,
+                    reporter.as_ref(),
+                )?;
+            }
+        }
+        Ok(())
+    });
+    let _ = std::fs::remove_dir_all(&check_dir);
+
+    let stages = timings.into_inner().expect("stage timings poisoned");
+    if !stages.is_empty() {
+        history.record(ReleaseRun {
+            tag: tag.clone(),
+            started_at,
+            total_secs: started.elapsed().as_secs(),
+            ok: result.is_ok(),
+            stages,
+        });
+        if let Err(err) = history.save(&repos_root) {
+            reporter.update(format!("warning: failed to save release history: {err:#}"));
         }
+    }
+    result?;
 
+    if let Some(download_dir) = &args.download_dir {
+        let stage_dir = download_dir.join(&tag);
         if args.dry_run {
             reporter.update(format!(
-                "[{}] (dry-run) would wait for assets: {:?}",
-                repo.name, expected
+                "(dry-run) would download all release assets into {}",
+                stage_dir.display()
             ));
         } else if let Some(ref gh) = gh {
+            args.cancel.check("Staging assets")?;
             reporter.step(
-                format!("Waiting for assets [{}]", repo.name),
-                format!("expected={:?}", expected),
+                "Staging assets".to_string(),
+                format!("into {}", stage_dir.display()),
             );
-            gh.wait_for_release_assets(
-                &repo.name,
+            let manifest = release_stage::stage_release_assets(
+                gh,
+                &ISO_CHAIN_REPOS,
                 &tag,
-                &expected,
-                args.poll_interval,
-                args.timeout,
-                reporter.as_ref(),
+                &stage_dir,
+                &definition.asset_checks,
+                &reporter,
             )
-            .with_context(|| format!("waiting for {} assets", repo.name))?;
+            .context("staging release assets")?;
+            reporter.update(format!("manifest: {}", manifest.display()));
+
+            // The smoke test boots under x86_64 QEMU/OVMF; other arches' ISOs are only
+            // checked statically.
+            if args.smoke_test {
+                let iso = stage_dir
+                    .join("installer-iso")
+                    .join(iso_asset_name(&version_without_v, Arch::X86_64));
+                let options = SmokeOptions {
+                    cancel: args.cancel.clone(),
+                    ..SmokeOptions::from_definition(&definition.smoke_test)
+                };
+                iso_smoke::smoke_test(&iso, &options, reporter.as_ref())?;
+                if args.arches.len() > 1 {
+                    reporter.update(format!(
+                        "smoke test only boots the x86_64 ISO; {} checked statically",
+                        arches_label(&args.arches[1..])
+                    ));
+                }
+            }
         }
     }
 
@@ -277,3 +1114,49 @@ pub fn run(args: ReleaseIsoArgs, reporter: DynReporter) -> Result<()> {
     reporter.ok("OK".to_string());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expected_assets_per_arch() {
+        assert_eq!(
+            expected_assets("installer-kernel", "1.2.3", &[Arch::X86_64, Arch::Aarch64]),
+            [
+                "BOOTX64.EFI",
+                "BOOTX64.EFI.sha256",
+                "BOOTAA64.EFI",
+                "BOOTAA64.EFI.sha256"
+            ]
+        );
+        assert_eq!(
+            expected_assets("truthdb", "1.2.3", &[Arch::Aarch64]),
+            [
+                "truthdb-v1.2.3-aarch64-linux-gnu.tar.gz",
+                "truthdb-v1.2.3-aarch64-linux-gnu.sha256"
+            ]
+        );
+        assert_eq!(
+            expected_assets("installer-iso", "1.2.3", &[Arch::X86_64, Arch::Aarch64]),
+            [
+                "truthdb-installer-v1.2.3.iso",
+                "truthdb-installer-v1.2.3.iso.sha256",
+                "truthdb-installer-v1.2.3-aarch64.iso",
+                "truthdb-installer-v1.2.3-aarch64.iso.sha256"
+            ]
+        );
+        assert!(expected_assets("docs", "1.2.3", &[Arch::X86_64]).is_empty());
+    }
+
+    #[test]
+    fn every_expected_asset_maps_back_to_its_arch() {
+        for arch in [Arch::X86_64, Arch::Aarch64] {
+            for repo in ISO_CHAIN_REPOS {
+                for asset in expected_assets(repo, "1.2.3", &[arch]) {
+                    assert_eq!(Arch::of_asset(&asset), Some(arch), "{asset}");
+                }
+            }
+        }
+    }
+}
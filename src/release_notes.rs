@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use semver::Version;
+
+use crate::changelog::{self, Changes};
+use crate::git::Repo;
+use crate::github::{GitHub, github_token};
+use crate::release_iso::{ISO_CHAIN_REPOS, default_repos_root, parse_and_normalize_version};
+use crate::reporter::DynReporter;
+
+const ISO_REPO: &str = "installer-iso";
+
+#[derive(Clone, Debug)]
+pub struct ReleaseNotesArgs {
+    pub version: String,
+    pub repos_root: Option<PathBuf>,
+    pub owner: String,
+    pub output: Option<PathBuf>,
+    pub publish: bool,
+}
+
+pub fn run(args: ReleaseNotesArgs, reporter: DynReporter) -> Result<()> {
+    let (tag, version_without_v) = parse_and_normalize_version(&args.version)?;
+    let version = Version::parse(&version_without_v)?;
+
+    reporter.step(
+        "Release Notes".to_string(),
+        format!("version={} (tag={})", version_without_v, tag),
+    );
+
+    let repos_root = match args.repos_root {
+        Some(p) => p,
+        None => default_repos_root()?,
+    };
+    reporter.update(format!("repos_root={}", repos_root.display()));
+
+    let mut sections = Vec::new();
+    for name in ISO_CHAIN_REPOS {
+        let repo = Repo::new(&args.owner, name, repos_root.join(name));
+        reporter.step(
+            format!("Collecting [{}]", repo.name),
+            format!("Reading history at {}", repo.dir.display()),
+        );
+
+        if !repo.dir.is_dir() {
+            bail!("repo directory not found: {}", repo.dir.display());
+        }
+
+        reporter.update("Fetching origin tags…".to_string());
+        repo.fetch_origin()?;
+
+        let Some(commit) = repo.local_tag_commit(&tag)? else {
+            bail!(
+                "{} has no tag {tag}; release notes can only be generated for tagged releases",
+                repo.dir.display()
+            );
+        };
+        let changes = Changes::collect(&repo, &version, &commit)?;
+        reporter.update(format!(
+            "{} commit(s) since {}",
+            changes.commits.len(),
+            changes
+                .previous_tag
+                .as_deref()
+                .unwrap_or("the first commit")
+        ));
+        sections.push((repo, changes));
+    }
+
+    let notes = render_markdown(&args.owner, &tag, &sections);
+
+    match &args.output {
+        Some(path) => {
+            fs::write(path, &notes)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            reporter.update(format!("wrote {}", path.display()));
+        }
+        None => print!("{notes}"),
+    }
+
+    if args.publish {
+        reporter.step(
+            "Publish".to_string(),
+            format!("Updating the {ISO_REPO} {tag} release body…"),
+        );
+        let gh = GitHub::new(args.owner.clone(), github_token())?;
+        gh.update_release_body(ISO_REPO, &tag, &notes)?;
+        reporter.update(format!(
+            "published release notes to {}/{ISO_REPO} {tag}",
+            args.owner
+        ));
+    }
+
+    reporter.ok("OK".to_string());
+    Ok(())
+}
+
+fn render_markdown(owner: &str, tag: &str, sections: &[(Repo, Changes)]) -> String {
+    let mut out = format!("# TruthDB installer ISO {tag}\n");
+
+    for (repo, changes) in sections {
+        out.push_str(&format!("\n## {}\n\n", repo.name));
+
+        match &changes.previous_tag {
+            Some(previous) => out.push_str(&format!(
+                "Changes since {previous} ([compare](https://github.com/{owner}/{}/compare/{previous}...{tag})):\n\n",
+                repo.name
+            )),
+            None => out.push_str("First release.\n\n"),
+        }
+
+        if changes.commits.is_empty() {
+            out.push_str("- No changes\n");
+            continue;
+        }
+
+        for line in changelog::render_grouped(&changes.commits, |title| format!("### {title}\n")) {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::Commit;
+
+    fn section(name: &str, previous_tag: Option<&str>, subjects: &[&str]) -> (Repo, Changes) {
+        let commits = subjects
+            .iter()
+            .enumerate()
+            .map(|(index, subject)| Commit {
+                sha: format!("{index}{}", "0".repeat(39)),
+                subject: subject.to_string(),
+            })
+            .collect();
+        (
+            Repo::new("Truthdb", name, name),
+            Changes {
+                previous_tag: previous_tag.map(str::to_string),
+                commits,
+            },
+        )
+    }
+
+    #[test]
+    fn renders_a_section_per_repo_with_compare_link() {
+        let notes = render_markdown(
+            "Truthdb",
+            "v1.2.3",
+            &[
+                section(
+                    "installer",
+                    Some("v1.2.2"),
+                    &["feat: aarch64 installer", "fix(disk): wipe GPT"],
+                ),
+                section("truthdb", Some("v1.2.2"), &["bump deps"]),
+            ],
+        );
+        assert_eq!(
+            notes,
+            "# TruthDB installer ISO v1.2.3\n\
+             \n## installer\n\n\
+             Changes since v1.2.2 ([compare](https://github.com/Truthdb/installer/compare/v1.2.2...v1.2.3)):\n\n\
+             ### Features\n\n- aarch64 installer (0000000)\n\n\
+             ### Bug Fixes\n\n- disk: wipe GPT (1000000)\n\
+             \n## truthdb\n\n\
+             Changes since v1.2.2 ([compare](https://github.com/Truthdb/truthdb/compare/v1.2.2...v1.2.3)):\n\n\
+             - bump deps (0000000)\n"
+        );
+    }
+
+    #[test]
+    fn first_release_has_no_compare_link() {
+        let notes = render_markdown(
+            "Truthdb",
+            "v0.1.0",
+            &[section("installer-iso", None, &["feat: first ISO"])],
+        );
+        assert!(
+            notes.contains("## installer-iso\n\nFirst release.\n\n"),
+            "{notes}"
+        );
+        assert!(!notes.contains("compare"), "{notes}");
+    }
+
+    #[test]
+    fn empty_range_says_no_changes() {
+        let notes = render_markdown(
+            "Truthdb",
+            "v1.2.3",
+            &[section("installer-kernel", Some("v1.2.2"), &[])],
+        );
+        assert!(notes.ends_with("...v1.2.3)):\n\n- No changes\n"), "{notes}");
+    }
+}
@@ -0,0 +1,353 @@
+use crate::arch::Arch;
+use crate::cancel::{self, CancelToken};
+use crate::changelog::{self, Changes};
+use crate::git::{Repo, TagOptions};
+use crate::github::{
+    FALLBACK_GITHUB_TOKEN_ENV, GitHub, LEGACY_GITHUB_TOKEN_ENV, PRIMARY_GITHUB_TOKEN_ENV,
+    github_token,
+};
+use crate::release_definition::{self, ReleaseDefinition};
+use crate::release_history::{self, EtaReporter, ReleaseHistory, ReleaseRun, StageTiming};
+use crate::release_iso::{
+    ISO_CHAIN_REPOS, WaitOptions, arches_label, default_repos_root, expected_assets,
+    parse_and_normalize_version, run_stages, stage_summary, wait_for_release,
+};
+use crate::reporter::DynReporter;
+use anyhow::{Context, Result, bail};
+use semver::Version;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct ReleasePromoteArgs {
+    pub from: String,
+    pub to: Option<String>,
+    pub repos_root: Option<PathBuf>,
+    pub release_definition: Option<PathBuf>,
+    pub arches: Vec<Arch>,
+    pub owner: String,
+    pub dry_run: bool,
+    pub resume: bool,
+    pub sign: bool,
+    pub poll_interval: Duration,
+    pub timeout: Duration,
+    pub cancel: CancelToken,
+}
+
+/// A repo's release candidate commit and whether the final tag still has to be created.
+struct Promotion {
+    rc_commit: String,
+    already_tagged: bool,
+}
+
+pub fn run(args: ReleasePromoteArgs, reporter: DynReporter) -> Result<()> {
+    promote(args, reporter).map_err(|err| match cancel::cancelled(&err) {
+        Some(cancelled) => anyhow::anyhow!("{cancelled}; resume with --resume"),
+        None => err,
+    })
+}
+
+fn promote(mut args: ReleasePromoteArgs, reporter: DynReporter) -> Result<()> {
+    args.arches.sort();
+    args.arches.dedup();
+    let (from_tag, from_version) = parse_and_normalize_version(&args.from)?;
+    let from = Version::parse(&from_version)?;
+    if from.pre.is_empty() {
+        bail!("--from {from_tag} is not a release candidate (expected e.g. v1.2.3-rc.1)");
+    }
+
+    let (to_tag, to_version) = match &args.to {
+        Some(to) => parse_and_normalize_version(to)?,
+        None => {
+            parse_and_normalize_version(&format!("{}.{}.{}", from.major, from.minor, from.patch))?
+        }
+    };
+    let to = Version::parse(&to_version)?;
+    if (to.major, to.minor, to.patch) != (from.major, from.minor, from.patch) || !to.pre.is_empty()
+    {
+        bail!("--to {to_tag} must be the final release of {from_tag}");
+    }
+
+    reporter.step(
+        "Initialize".to_string(),
+        format!(
+            "promote {from_tag} -> {to_tag}\nmode={}{}{}\narch={}",
+            if args.dry_run { "dry-run" } else { "live" },
+            if args.resume { ", resume" } else { "" },
+            if args.sign { ", signed tags" } else { "" },
+            arches_label(&args.arches)
+        ),
+    );
+
+    let repos_root = match args.repos_root {
+        Some(p) => p,
+        None => default_repos_root()?,
+    };
+    reporter.update(format!("repos_root={}", repos_root.display()));
+
+    let definition = ReleaseDefinition::load(args.release_definition.as_deref())?;
+    definition.check_dependencies(&ISO_CHAIN_REPOS)?;
+    let mut history = ReleaseHistory::load(&repos_root)?;
+    let estimates = history.estimates(&ISO_CHAIN_REPOS);
+
+    let repos: Vec<Repo> = ISO_CHAIN_REPOS
+        .iter()
+        .map(|name| Repo::new(&args.owner, *name, repos_root.join(name)))
+        .collect();
+
+    // Preflight: every repo must have the candidate on origin, and the final tag must be
+    // free (or, with --resume, already on exactly the candidate commit).
+    let mut promotions = BTreeMap::new();
+    let mut candidate_versions = Vec::new();
+    for repo in &repos {
+        let stage = format!("Preflight [{}]", repo.name);
+        args.cancel.check(&stage)?;
+        reporter.step(stage, format!("Checking repo at {}", repo.dir.display()));
+
+        if !repo.dir.is_dir() {
+            bail!("repo directory not found: {}", repo.dir.display());
+        }
+
+        reporter.update("Verifying origin remote…".to_string());
+        repo.ensure_origin_matches_expected()?;
+
+        reporter.update("Fetching origin tags…".to_string());
+        repo.fetch_origin()?;
+
+        reporter.update(format!("Resolving {from_tag}…"));
+        let rc_commit = repo
+            .remote_tag_commit(&from_tag)?
+            .with_context(|| format!("{} has no tag {from_tag} on origin", repo.dir.display()))?;
+        if let Some(local) = repo.local_tag_commit(&from_tag)?
+            && local != rc_commit
+        {
+            bail!(
+                "{} local tag {from_tag} ({local}) differs from origin ({rc_commit}); fix the local tag first",
+                repo.dir.display()
+            );
+        }
+
+        let head = repo.head_commit()?;
+        if head == rc_commit {
+            reporter.update(format!("HEAD is {from_tag}"));
+        } else {
+            reporter.update(format!(
+                "HEAD has moved past {from_tag}; tagging the candidate commit {}",
+                &rc_commit[..rc_commit.len().min(12)]
+            ));
+        }
+
+        // The final tag reuses the candidate's commit, so its version files still carry the
+        // candidate's version (release-iso requires that for the rc).
+        let version_files = definition.version_files(&repo.name);
+        if !version_files.is_empty() {
+            reporter.update("Checking version files…".to_string());
+            let mismatches = release_definition::check_version_files(version_files, &to, |path| {
+                repo.read_file_at(&rc_commit, path)
+            })?;
+            if !mismatches.is_empty() {
+                candidate_versions.push((repo.name.as_str(), mismatches));
+            }
+        }
+
+        let already_tagged = match repo.remote_tag_commit(&to_tag)? {
+            Some(commit) if commit == rc_commit && args.resume => true,
+            Some(commit) if commit == rc_commit => bail!(
+                "{} already has {to_tag} on origin at the candidate commit. Re-run with --resume to continue.",
+                repo.dir.display()
+            ),
+            Some(commit) => bail!(
+                "{} already has {to_tag} on origin at {commit}, not at {from_tag} ({rc_commit})",
+                repo.dir.display()
+            ),
+            None => false,
+        };
+
+        if !already_tagged {
+            match repo.local_tag_commit(&to_tag)? {
+                Some(commit) if commit == rc_commit && args.resume => {}
+                Some(commit) => bail!(
+                    "{} already has local tag {to_tag} at {commit}; delete it or pass --resume if it points at {from_tag}",
+                    repo.dir.display()
+                ),
+                None => {}
+            }
+
+            if args.sign {
+                reporter.update("Checking tag signing key…".to_string());
+                let key = repo.ensure_signing_key_configured()?;
+                reporter.update(format!("signing with {key}"));
+            }
+        }
+
+        promotions.insert(
+            repo.name.clone(),
+            Promotion {
+                rc_commit,
+                already_tagged,
+            },
+        );
+    }
+
+    let version_warning = (!candidate_versions.is_empty()).then(|| {
+        candidate_versions
+            .iter()
+            .flat_map(|(repo, mismatches)| {
+                mismatches
+                    .iter()
+                    .map(move |m| format!("  {repo}: {}", m.describe()))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    });
+    if let Some(summary) = &version_warning {
+        reporter.step(
+            "Version files".to_string(),
+            format!(
+                "warning: {to_tag} is tagged on the {from_tag} commits, whose version files don't say {to_tag}, so binaries built from it won't report {to_tag}:\n{summary}\n\nTo ship binaries that report {to_tag}, bump these files and release {to_tag} with release-iso instead."
+            ),
+        );
+    }
+
+    let token = github_token();
+    if !args.dry_run && token.is_empty() {
+        bail!(
+            "missing {}, {}, or {}. This is required to poll release assets after tagging.",
+            PRIMARY_GITHUB_TOKEN_ENV,
+            FALLBACK_GITHUB_TOKEN_ENV,
+            LEGACY_GITHUB_TOKEN_ENV
+        );
+    }
+    let gh = if args.dry_run || token.is_empty() {
+        None
+    } else {
+        Some(GitHub::new(args.owner.clone(), token)?.with_cancel(args.cancel.clone()))
+    };
+    let wait = WaitOptions {
+        poll_interval: args.poll_interval,
+        timeout: args.timeout,
+    };
+
+    let mut summary = stage_summary(&repos, &definition);
+    if let Some(eta) = release_history::overall_eta(&ISO_CHAIN_REPOS, &definition, &estimates) {
+        summary.push_str(&format!(
+            "\n\nETA {} from previous releases",
+            release_history::format_duration(eta)
+        ));
+    }
+    reporter.step(format!("Promoting {from_tag} -> {to_tag}"), summary);
+    let started_at = release_history::unix_now();
+    let started = Instant::now();
+    let timings: Mutex<Vec<StageTiming>> = Mutex::new(Vec::new());
+    // Tags are created and pushed one repo at a time, since signing or pushing may prompt.
+    let tagging = Mutex::new(());
+    let result = run_stages(&repos, &definition, &reporter, |repo, reporter| {
+        let promotion = promotions
+            .get(&repo.name)
+            .context("promotion missing after preflight")?;
+        let mut pushed_at = None;
+        args.cancel.check("Tagging")?;
+        reporter.step("Tagging".to_string(), format!("{from_tag} -> {to_tag}"));
+
+        if promotion.already_tagged {
+            reporter.update(format!(
+                "{to_tag} already on origin at the candidate; skipping create/push"
+            ));
+        } else if args.dry_run {
+            reporter.update(format!(
+                "(dry-run) would create {} tag {to_tag} at {} and push",
+                if args.sign { "signed" } else { "annotated" },
+                &promotion.rc_commit[..promotion.rc_commit.len().min(12)]
+            ));
+        } else {
+            let _tagging = tagging.lock().expect("tagging lock poisoned");
+            if repo.local_tag_commit(&to_tag)?.is_none() {
+                let changes = Changes::collect(repo, &to, &promotion.rc_commit)?;
+                let mut message = changelog::render_tag_message(&to_tag, &changes);
+                message.push_str(&format!("\nPromoted from {from_tag}.\n"));
+
+                reporter.update(if args.sign {
+                    "Creating signed tag…".to_string()
+                } else {
+                    "Creating annotated tag…".to_string()
+                });
+                repo.create_annotated_tag(
+                    &to_tag,
+                    &TagOptions {
+                        sign: args.sign,
+                        message: Some(message),
+                        target: Some(promotion.rc_commit.clone()),
+                    },
+                )?;
+            }
+
+            if args.sign {
+                reporter.update("Verifying tag signature…".to_string());
+                repo.verify_tag_signature(&to_tag)?;
+            }
+
+            args.cancel.check("Pushing tag")?;
+            reporter.update("Pushing tag to origin…".to_string());
+            repo.push_tag(&to_tag)?;
+            pushed_at = Some(Instant::now());
+        }
+
+        let expected = expected_assets(&repo.name, &to_version, &args.arches);
+        if expected.is_empty() {
+            return Ok(());
+        }
+
+        if args.dry_run {
+            reporter.update(format!("(dry-run) would wait for assets: {:?}", expected));
+        } else if let Some(ref gh) = gh {
+            match pushed_at {
+                Some(pushed_at) => {
+                    let reporter =
+                        EtaReporter::wrap(reporter, estimates.get(&repo.name), pushed_at);
+                    let result = wait_for_release(gh, repo, &to_tag, &expected, &wait, &reporter);
+                    timings
+                        .lock()
+                        .expect("stage timings poisoned")
+                        .push(StageTiming {
+                            repo: repo.name.clone(),
+                            secs: pushed_at.elapsed().as_secs(),
+                            ok: result.is_ok(),
+                        });
+                    result?;
+                }
+                // Pushed by an earlier run, so there's no push time to measure from.
+                None => wait_for_release(gh, repo, &to_tag, &expected, &wait, reporter)?,
+            }
+        }
+        Ok(())
+    });
+
+    let stages = timings.into_inner().expect("stage timings poisoned");
+    if !stages.is_empty() {
+        history.record(ReleaseRun {
+            tag: to_tag.clone(),
+            started_at,
+            total_secs: started.elapsed().as_secs(),
+            ok: result.is_ok(),
+            stages,
+        });
+        if let Err(err) = history.save(&repos_root) {
+            reporter.update(format!("warning: failed to save release history: {err:#}"));
+        }
+    }
+    result?;
+
+    reporter.step(
+        "Complete".to_string(),
+        match &version_warning {
+            Some(_) => format!(
+                "{to_tag} is tagged on the {from_tag} commits in every repo.\nwarning: some version files don't report {to_tag} (see Version files)."
+            ),
+            None => format!("{to_tag} is tagged on the {from_tag} commits in every repo."),
+        },
+    );
+    reporter.ok("OK".to_string());
+    Ok(())
+}
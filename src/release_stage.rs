@@ -0,0 +1,209 @@
+use crate::asset_check;
+use crate::checksum::{parse_sha256_file, sha256_file};
+use crate::github::{GitHub, ReleaseAsset};
+use crate::release_definition::AssetCheck;
+use crate::reporter::{DynReporter, KeyedReporter, Reporter};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Everything staged for one release, written to `<dir>/manifest.json`.
+#[derive(Debug, Serialize)]
+pub struct StageManifest {
+    pub tag: String,
+    pub assets: Vec<StagedAsset>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StagedAsset {
+    pub repo: String,
+    pub tag: String,
+    pub name: String,
+    /// Relative to the staging directory.
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// Whether the digest was checked against a published `.sha256` asset.
+    pub verified: bool,
+    /// Content checks from the release definition that passed (e.g. `tar-gz`).
+    pub checks: Vec<String>,
+}
+
+/// Downloads every release asset of `repos` at `tag` into `dir/<repo>/` and writes the manifest.
+///
+/// Files already present with the right size are reused, and partial downloads resume, so
+/// re-running after an interruption only fetches what's missing.
+pub fn stage_release_assets(
+    gh: &GitHub,
+    repos: &[&str],
+    tag: &str,
+    dir: &Path,
+    checks: &[AssetCheck],
+    reporter: &DynReporter,
+) -> Result<PathBuf> {
+    let mut manifest = StageManifest {
+        tag: tag.to_string(),
+        assets: Vec::new(),
+    };
+
+    for repo in repos {
+        let reporter: DynReporter = Arc::new(KeyedReporter::new(reporter.clone(), *repo));
+        manifest.assets.extend(stage_repo_assets(
+            gh,
+            repo,
+            tag,
+            &dir.join(repo),
+            checks,
+            |_| true,
+            reporter.as_ref(),
+        )?);
+    }
+
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let text =
+        serde_json::to_string_pretty(&manifest).context("failed to serialize stage manifest")?;
+    fs::write(&manifest_path, text)
+        .with_context(|| format!("failed to write {}", manifest_path.display()))?;
+    Ok(manifest_path)
+}
+
+/// Downloads the assets of `repo` at `tag` that have content checks (plus the checksum
+/// files) into `repo_dir`, verifies their digests, and runs the checks.
+pub fn check_repo_assets(
+    gh: &GitHub,
+    repo: &str,
+    tag: &str,
+    repo_dir: &Path,
+    checks: &[AssetCheck],
+    reporter: &dyn Reporter,
+) -> Result<()> {
+    stage_repo_assets(
+        gh,
+        repo,
+        tag,
+        repo_dir,
+        checks,
+        |name| name.ends_with(".sha256") || checks.iter().any(|check| check.matches(name)),
+        reporter,
+    )
+    .map(|_| ())
+}
+
+fn stage_repo_assets(
+    gh: &GitHub,
+    repo: &str,
+    tag: &str,
+    repo_dir: &Path,
+    checks: &[AssetCheck],
+    wanted: impl Fn(&str) -> bool,
+    reporter: &dyn Reporter,
+) -> Result<Vec<StagedAsset>> {
+    let release = gh
+        .get_release_by_tag(repo, tag)?
+        .with_context(|| format!("{repo} has no release for {tag}"))?;
+
+    fs::create_dir_all(repo_dir)
+        .with_context(|| format!("failed to create {}", repo_dir.display()))?;
+
+    let assets: Vec<&ReleaseAsset> = release
+        .assets
+        .iter()
+        .filter(|asset| wanted(&asset.name))
+        .collect();
+
+    for asset in &assets {
+        let dest = repo_dir.join(&asset.name);
+        let complete = fs::metadata(&dest).is_ok_and(|meta| meta.len() == asset.size);
+        if complete {
+            reporter.update(format!("{} already staged", asset.name));
+        } else {
+            reporter.update(format!(
+                "downloading {} ({} bytes)…",
+                asset.name, asset.size
+            ));
+            gh.download_asset_to(asset, &dest)?;
+        }
+    }
+
+    let mut staged = Vec::new();
+    for asset in &assets {
+        let dest = repo_dir.join(&asset.name);
+        let sha256 = sha256_file(&dest)?;
+        let verified = match published_digest(repo_dir, &release.assets, &asset.name)? {
+            Some(expected) if expected == sha256 => true,
+            Some(expected) => bail!(
+                "{repo} {}: sha256 mismatch (expected {expected}, got {sha256}). Delete {} and re-run.",
+                asset.name,
+                dest.display()
+            ),
+            None => {
+                if !asset.name.ends_with(".sha256") {
+                    reporter.update(format!("warning: no published checksum for {}", asset.name));
+                }
+                false
+            }
+        };
+
+        let mut passed = Vec::new();
+        for check in checks.iter().filter(|check| check.matches(&asset.name)) {
+            reporter.update(format!("checking {} ({})…", asset.name, check.kind));
+            let summary = asset_check::run_check(&dest, check).with_context(|| {
+                format!(
+                    "{repo} {}: {} check failed (file: {})",
+                    asset.name,
+                    check.kind,
+                    dest.display()
+                )
+            })?;
+            reporter.update(format!("{}: {summary}", asset.name));
+            passed.push(check.kind.to_string());
+        }
+
+        staged.push(StagedAsset {
+            repo: repo.to_string(),
+            tag: tag.to_string(),
+            name: asset.name.clone(),
+            path: Path::new(repo).join(&asset.name),
+            size: asset.size,
+            sha256,
+            verified,
+            checks: passed,
+        });
+    }
+    Ok(staged)
+}
+
+/// Digest for `name` from the release's checksum asset, if one was published.
+///
+/// Releases publish either `<asset>.sha256` or, for tarballs, `<stem>.sha256`.
+fn published_digest(
+    repo_dir: &Path,
+    assets: &[ReleaseAsset],
+    name: &str,
+) -> Result<Option<String>> {
+    if name.ends_with(".sha256") {
+        return Ok(None);
+    }
+
+    let mut candidates = vec![format!("{name}.sha256")];
+    if let Some(stem) = name.strip_suffix(".tar.gz") {
+        candidates.push(format!("{stem}.sha256"));
+    }
+
+    for candidate in candidates {
+        if !assets.iter().any(|asset| asset.name == candidate) {
+            continue;
+        }
+        let path = repo_dir.join(&candidate);
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        if let Some(digest) = parse_sha256_file(&text, name) {
+            return Ok(Some(digest));
+        }
+    }
+    Ok(None)
+}
@@ -1,5 +1,7 @@
+use std::io::{BufRead, IsTerminal, Write};
 use std::sync::Arc;
 
+use anyhow::{Context, Result, bail};
 use crossbeam_channel::Sender;
 
 use crate::tui::UiEvent;
@@ -9,6 +11,10 @@ pub trait Reporter: Send + Sync {
     fn update(&self, body: String);
     fn ok(&self, msg: String);
     fn error(&self, msg: String);
+    /// Latest progress line for a concurrent sub-task (e.g. one repo's clone).
+    fn progress(&self, key: String, msg: String);
+    /// Asks a yes/no question and blocks until it is answered.
+    fn confirm(&self, prompt: String) -> Result<bool>;
 }
 
 pub type DynReporter = Arc<dyn Reporter>;
@@ -47,6 +53,29 @@ impl Reporter for PlainReporter {
     fn error(&self, msg: String) {
         eprintln!("ERROR: {}", msg);
     }
+
+    fn progress(&self, key: String, msg: String) {
+        eprintln!("[{}] {}", key, msg);
+    }
+
+    fn confirm(&self, prompt: String) -> Result<bool> {
+        if !std::io::stdin().is_terminal() {
+            bail!("stdin is not a terminal; re-run with --yes to skip confirmation");
+        }
+
+        eprint!("{prompt} [y/N] ");
+        std::io::stderr().flush().ok();
+
+        let mut answer = String::new();
+        std::io::stdin()
+            .lock()
+            .read_line(&mut answer)
+            .context("failed to read confirmation")?;
+        Ok(matches!(
+            answer.trim().to_ascii_lowercase().as_str(),
+            "y" | "yes"
+        ))
+    }
 }
 
 #[derive(Clone)]
@@ -81,4 +110,58 @@ impl Reporter for ChannelReporter {
     fn error(&self, msg: String) {
         self.send(UiEvent::SetError { msg });
     }
+
+    fn progress(&self, key: String, msg: String) {
+        self.send(UiEvent::SetProgress { key, msg });
+    }
+
+    fn confirm(&self, prompt: String) -> Result<bool> {
+        let (reply, answer) = crossbeam_channel::bounded(1);
+        self.send(UiEvent::Confirm { prompt, reply });
+        // If the UI exits without answering, treat it as a "no".
+        Ok(answer.recv().unwrap_or(false))
+    }
+}
+
+/// Reports one of several concurrent sub-tasks on its own progress line, so their steps
+/// don't overwrite each other.
+pub struct KeyedReporter {
+    inner: DynReporter,
+    key: String,
+}
+
+impl KeyedReporter {
+    pub fn new(inner: DynReporter, key: impl Into<String>) -> Self {
+        Self {
+            inner,
+            key: key.into(),
+        }
+    }
+}
+
+impl Reporter for KeyedReporter {
+    fn step(&self, title: String, _body: String) {
+        self.inner.progress(self.key.clone(), title);
+    }
+
+    fn update(&self, msg: String) {
+        self.inner.progress(self.key.clone(), msg);
+    }
+
+    fn ok(&self, msg: String) {
+        self.inner.progress(self.key.clone(), msg);
+    }
+
+    fn error(&self, msg: String) {
+        self.inner
+            .progress(self.key.clone(), format!("FAILED: {msg}"));
+    }
+
+    fn progress(&self, key: String, msg: String) {
+        self.inner.progress(key, msg);
+    }
+
+    fn confirm(&self, prompt: String) -> Result<bool> {
+        self.inner.confirm(prompt)
+    }
 }
@@ -0,0 +1,204 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+use flate2::read::GzDecoder;
+
+use crate::checksum::{parse_sha256_file, sha256_hex};
+use crate::github::{GitHub, github_token};
+use crate::launcher::{
+    BIN_DIR_NAME, INSTALLED_BINARY_NAME, WRAPPER_NAME, binary_version, stage_installed_binary,
+    stamp_from_version_output,
+};
+use crate::reporter::DynReporter;
+use crate::workspace_update::resolve_workspace_root;
+
+const ORCHESTRATOR_REPO: &str = "orchestrator";
+const BINARY_NAME: &str = "orchestrator";
+const OUTPUT_TAIL_LINES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SelfUpdateSource {
+    /// Build the sibling `orchestrator/` checkout with cargo.
+    Build,
+    /// Download the latest orchestrator GitHub release.
+    Release,
+}
+
+#[derive(Debug, Clone)]
+pub struct SelfUpdateArgs {
+    pub workspace_root: Option<PathBuf>,
+    pub owner: String,
+    pub source: SelfUpdateSource,
+    pub keep_versions: usize,
+}
+
+pub fn run(args: SelfUpdateArgs, reporter: DynReporter) -> Result<()> {
+    reporter.step(
+        "Self Update".to_string(),
+        "Replacing the workspace launcher binary with a newer orchestrator.".to_string(),
+    );
+
+    let workspace_root = resolve_workspace_root(args.workspace_root)?;
+    let bin_dir = workspace_root.join(BIN_DIR_NAME);
+    let installed_binary = bin_dir.join(INSTALLED_BINARY_NAME);
+    reporter.update(format!("workspace_root={}", workspace_root.display()));
+
+    let old_version = if installed_binary.is_file() {
+        binary_version(&installed_binary).unwrap_or_else(|_| "unknown".to_string())
+    } else {
+        "not installed".to_string()
+    };
+
+    let contents = match args.source {
+        SelfUpdateSource::Build => build_from_source(&workspace_root, &reporter)?,
+        SelfUpdateSource::Release => download_latest_release(&args.owner, &reporter)?,
+    };
+
+    reporter.step(
+        "Verify".to_string(),
+        format!("Checking the new binary runs ({} bytes)…", contents.len()),
+    );
+    let staged = stage_installed_binary(&bin_dir, &contents)?;
+    let new_version = binary_version(staged.path())
+        .context("new orchestrator binary failed verification; launcher left unchanged")?;
+    if !new_version.starts_with(BINARY_NAME) {
+        bail!(
+            "new binary reported unexpected version output '{new_version}'; launcher left unchanged"
+        );
+    }
+
+    let stamp = stamp_from_version_output(&new_version)
+        .with_context(|| format!("can't derive a version stamp from '{new_version}'"))?;
+    staged.activate(&stamp, args.keep_versions)?;
+
+    if !bin_dir.join(WRAPPER_NAME).is_file() {
+        reporter.update(format!(
+            "{} is missing; run workspace-update to install it",
+            bin_dir.join(WRAPPER_NAME).display()
+        ));
+    }
+
+    reporter.step(
+        "Complete".to_string(),
+        format!(
+            "launcher: {}\nold: {}\nnew: {}",
+            installed_binary.display(),
+            old_version,
+            new_version
+        ),
+    );
+    reporter.ok(format!("{old_version} -> {new_version}"));
+    Ok(())
+}
+
+fn build_from_source(workspace_root: &Path, reporter: &DynReporter) -> Result<Vec<u8>> {
+    let repo_dir = workspace_root.join(ORCHESTRATOR_REPO);
+    let manifest = repo_dir.join("Cargo.toml");
+    if !manifest.is_file() {
+        bail!(
+            "no orchestrator checkout found at {}. Run workspace-update first or use --source release.",
+            repo_dir.display()
+        );
+    }
+
+    reporter.step(
+        "Build".to_string(),
+        format!("cargo build --release in {}", repo_dir.display()),
+    );
+    let output = Command::new("cargo")
+        .current_dir(&repo_dir)
+        .args(["build", "--release", "--manifest-path"])
+        .arg(&manifest)
+        .output()
+        .with_context(|| format!("failed to run cargo build in {}", repo_dir.display()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let lines: Vec<&str> = stderr.lines().collect();
+        let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+        bail!(
+            "cargo build failed in {} (exit={}):\n{}",
+            repo_dir.display(),
+            output.status,
+            tail.join("\n")
+        );
+    }
+
+    let target_dir = match env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) => repo_dir.join(dir),
+        None => repo_dir.join("target"),
+    };
+    let binary = target_dir.join("release").join(BINARY_NAME);
+    fs::read(&binary).with_context(|| format!("failed to read built binary {}", binary.display()))
+}
+
+fn download_latest_release(owner: &str, reporter: &DynReporter) -> Result<Vec<u8>> {
+    if !(cfg!(target_os = "linux") && cfg!(target_arch = "x86_64")) {
+        bail!("orchestrator releases only ship x86_64-linux-gnu binaries; use --source build");
+    }
+
+    reporter.step(
+        "Download".to_string(),
+        format!("Looking up the latest {owner}/{ORCHESTRATOR_REPO} release…"),
+    );
+    let gh = GitHub::new(owner.to_string(), github_token())?;
+    let release = gh
+        .get_latest_release(ORCHESTRATOR_REPO)?
+        .with_context(|| format!("{owner}/{ORCHESTRATOR_REPO} has no published release"))?;
+
+    let version = release
+        .tag_name
+        .strip_prefix('v')
+        .unwrap_or(&release.tag_name);
+    let archive_name = format!("{BINARY_NAME}-v{version}-x86_64-linux-gnu.tar.gz");
+    let checksum_name = format!("{BINARY_NAME}-v{version}-x86_64-linux-gnu.sha256");
+
+    let find_asset = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .with_context(|| format!("release {} is missing asset {name}", release.tag_name))
+    };
+    let archive_asset = find_asset(&archive_name)?;
+    let checksum_asset = find_asset(&checksum_name)?;
+
+    reporter.update(format!("downloading {archive_name}…"));
+    let archive = gh.download_asset(archive_asset)?;
+    reporter.update(format!("downloading {checksum_name}…"));
+    let checksum_text = String::from_utf8(gh.download_asset(checksum_asset)?)
+        .with_context(|| format!("{checksum_name} is not valid UTF-8"))?;
+
+    let binary = extract_binary(&archive)
+        .with_context(|| format!("failed to extract {BINARY_NAME} from {archive_name}"))?;
+
+    let expected = parse_sha256_file(&checksum_text, BINARY_NAME)
+        .with_context(|| format!("{checksum_name} has no digest for {BINARY_NAME}"))?;
+    let actual = sha256_hex(&binary);
+    if actual != expected {
+        bail!("checksum mismatch for {BINARY_NAME}: expected {expected}, got {actual}");
+    }
+    reporter.update(format!("sha256 verified ({actual})"));
+
+    Ok(binary)
+}
+
+fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    let mut tar = tar::Archive::new(GzDecoder::new(archive));
+    for entry in tar.entries().context("failed to read archive")? {
+        let mut entry = entry.context("failed to read archive entry")?;
+        let path = entry.path().context("invalid archive entry path")?;
+        if path.file_name().and_then(|name| name.to_str()) == Some(BINARY_NAME) {
+            let mut contents = Vec::new();
+            entry
+                .read_to_end(&mut contents)
+                .context("failed to read archive entry")?;
+            return Ok(contents);
+        }
+    }
+    bail!("archive does not contain {BINARY_NAME}")
+}
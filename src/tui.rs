@@ -1,7 +1,7 @@
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
-use crossbeam_channel::Receiver;
+use crossbeam_channel::{Receiver, Sender};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
@@ -56,12 +56,34 @@ pub struct RepoStatusRow {
 
 #[derive(Debug, Clone)]
 pub enum UiEvent {
-    SetStep { title: String, body: String },
-    UpdateBody { body: String },
-    SetOk { msg: String },
-    SetError { msg: String },
-    SetRepos { rows: Vec<RepoStatusRow> },
-    Finished { ok: bool },
+    SetStep {
+        title: String,
+        body: String,
+    },
+    UpdateBody {
+        body: String,
+    },
+    SetOk {
+        msg: String,
+    },
+    SetError {
+        msg: String,
+    },
+    SetRepos {
+        rows: Vec<RepoStatusRow>,
+    },
+    SetProgress {
+        key: String,
+        msg: String,
+    },
+    /// Ask the user a yes/no question; the answer goes back on `reply`.
+    Confirm {
+        prompt: String,
+        reply: Sender<bool>,
+    },
+    Finished {
+        ok: bool,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -78,6 +100,8 @@ struct AppState {
     ok_msg: String,
     error_msg: Option<String>,
     repos: Vec<RepoStatusRow>,
+    progress: Vec<(String, String)>,
+    confirm: Option<(String, Sender<bool>)>,
     help_scroll: u16,
     focus: Focus,
     finished: Option<bool>,
@@ -92,6 +116,8 @@ impl AppState {
             ok_msg: "OK".to_string(),
             error_msg: None,
             repos: Vec::new(),
+            progress: Vec::new(),
+            confirm: None,
             help_scroll: 0,
             focus: Focus::None,
             finished: None,
@@ -101,6 +127,7 @@ impl AppState {
 
 const HELP_TEXT: &str = r#"Keys
   q / Esc       Quit
+  y / n        Answer a confirmation prompt
   Tab          Focus help
   Up/Down      Scroll help
   PgUp/PgDn    Scroll help faster
@@ -186,6 +213,15 @@ fn handle_ui_event(state: &mut AppState, ev: UiEvent) {
         UiEvent::SetRepos { rows } => {
             state.repos = rows;
         }
+        UiEvent::SetProgress { key, msg } => {
+            match state.progress.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = msg,
+                None => state.progress.push((key, msg)),
+            }
+        }
+        UiEvent::Confirm { prompt, reply } => {
+            state.confirm = Some((prompt, reply));
+        }
         UiEvent::Finished { ok } => {
             state.finished = Some(ok);
             if ok {
@@ -197,6 +233,20 @@ fn handle_ui_event(state: &mut AppState, ev: UiEvent) {
 }
 
 fn handle_key(state: &mut AppState, key: KeyEvent) -> bool {
+    if state.confirm.is_some() {
+        let answer = match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => Some(true),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+            _ => None,
+        };
+        if let Some(answer) = answer
+            && let Some((_, reply)) = state.confirm.take()
+        {
+            let _ = reply.send(answer);
+            return false;
+        }
+    }
+
     match (key.code, key.modifiers) {
         (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => return true,
         (KeyCode::Char('c'), KeyModifiers::CONTROL) => return true,
@@ -276,11 +326,21 @@ fn ui(f: &mut ratatui::Frame, state: &AppState) {
     render_status(f, top_cols[1], state);
     render_repos(f, mid_cols[0], state);
     render_help(f, mid_cols[1], state);
-    render_bottom_left(f, bottom_cols[0]);
+    render_bottom_left(f, bottom_cols[0], state);
     render_bottom_right(f, bottom_cols[1]);
 }
 
-fn render_bottom_left(f: &mut ratatui::Frame, area: Rect) {
+fn render_bottom_left(f: &mut ratatui::Frame, area: Rect, state: &AppState) {
+    if let Some((prompt, _)) = &state.confirm {
+        render_confirm(f, area, state, prompt);
+        return;
+    }
+
+    if !state.progress.is_empty() {
+        render_progress(f, area, state);
+        return;
+    }
+
     let block = base_block("Pane A");
     let para = Paragraph::new("Reserved")
         .block(block)
@@ -288,6 +348,51 @@ fn render_bottom_left(f: &mut ratatui::Frame, area: Rect) {
     f.render_widget(para, area);
 }
 
+/// Shows the current step's body (e.g. a release plan) above the pending question.
+fn render_confirm(f: &mut ratatui::Frame, area: Rect, state: &AppState, prompt: &str) {
+    let block =
+        base_block(state.step_title.clone()).border_style(Style::default().fg(Color::Yellow));
+
+    let mut lines: Vec<Line> = state
+        .step_body
+        .lines()
+        .map(|l| Line::raw(l.to_string()))
+        .collect();
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(
+        format!("{prompt}  [y] yes  [n] no"),
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    ));
+
+    let para = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: false });
+    f.render_widget(para, area);
+}
+
+fn render_progress(f: &mut ratatui::Frame, area: Rect, state: &AppState) {
+    let block = base_block("Progress");
+
+    let width = state
+        .progress
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    let lines: Vec<Line> = state
+        .progress
+        .iter()
+        .map(|(key, msg)| Line::raw(format!("{key:<width$}  {msg}")))
+        .collect();
+
+    let para = Paragraph::new(Text::from(lines))
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(para, area);
+}
+
 fn render_bottom_right(f: &mut ratatui::Frame, area: Rect) {
     let block = base_block("Pane B");
     let para = Paragraph::new("Reserved")
@@ -0,0 +1,104 @@
+use semver::{BuildMetadata, Prerelease, Version};
+
+/// Which part of the highest existing release version to increment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BumpKind {
+    Major,
+    Minor,
+    Patch,
+    /// Next `-rc.N` of the current prerelease, or `-rc.1` of the next patch.
+    Prerelease,
+}
+
+/// Prerelease label used when starting a new prerelease series.
+const PRERELEASE_LABEL: &str = "rc";
+
+/// Computes the version after `current`.
+///
+/// Follows the usual SemVer convention for prereleases: bumping `1.3.0-rc.2` by
+/// `minor` releases `1.3.0` rather than skipping to `1.4.0`.
+pub fn next_version(current: &Version, kind: BumpKind) -> Version {
+    let is_pre = !current.pre.is_empty();
+    let mut next = Version::new(current.major, current.minor, current.patch);
+
+    match kind {
+        BumpKind::Major => {
+            if !(is_pre && current.minor == 0 && current.patch == 0) {
+                next = Version::new(current.major + 1, 0, 0);
+            }
+        }
+        BumpKind::Minor => {
+            if !(is_pre && current.patch == 0) {
+                next = Version::new(current.major, current.minor + 1, 0);
+            }
+        }
+        BumpKind::Patch => {
+            if !is_pre {
+                next.patch += 1;
+            }
+        }
+        BumpKind::Prerelease => {
+            if is_pre {
+                next.pre = next_prerelease(&current.pre);
+            } else {
+                next.patch += 1;
+                next.pre = Prerelease::new(&format!("{PRERELEASE_LABEL}.1"))
+                    .expect("static prerelease label is valid");
+            }
+        }
+    }
+
+    next.build = BuildMetadata::EMPTY;
+    next
+}
+
+/// Increments the trailing numeric identifier (`rc.1` -> `rc.2`), or appends `.1`.
+fn next_prerelease(pre: &Prerelease) -> Prerelease {
+    let mut parts: Vec<String> = pre.as_str().split('.').map(str::to_string).collect();
+    match parts.last().and_then(|last| last.parse::<u64>().ok()) {
+        Some(n) => {
+            let last = parts.len() - 1;
+            parts[last] = (n + 1).to_string();
+        }
+        None => parts.push("1".to_string()),
+    }
+    Prerelease::new(&parts.join(".")).expect("incremented prerelease stays valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump(current: &str, kind: BumpKind) -> String {
+        next_version(&Version::parse(current).unwrap(), kind).to_string()
+    }
+
+    #[test]
+    fn bumps_final_releases() {
+        assert_eq!(bump("1.2.3", BumpKind::Major), "2.0.0");
+        assert_eq!(bump("1.2.3", BumpKind::Minor), "1.3.0");
+        assert_eq!(bump("1.2.3", BumpKind::Patch), "1.2.4");
+        assert_eq!(bump("1.2.3", BumpKind::Prerelease), "1.2.4-rc.1");
+    }
+
+    #[test]
+    fn releases_prereleases_instead_of_skipping_them() {
+        assert_eq!(bump("2.0.0-rc.1", BumpKind::Major), "2.0.0");
+        assert_eq!(bump("1.3.0-rc.2", BumpKind::Minor), "1.3.0");
+        assert_eq!(bump("1.2.4-rc.1", BumpKind::Patch), "1.2.4");
+        assert_eq!(bump("1.3.1-rc.1", BumpKind::Minor), "1.4.0");
+        assert_eq!(bump("1.3.0-rc.1", BumpKind::Major), "2.0.0");
+    }
+
+    #[test]
+    fn increments_prerelease_numbers() {
+        assert_eq!(bump("1.2.4-rc.1", BumpKind::Prerelease), "1.2.4-rc.2");
+        assert_eq!(bump("1.2.4-rc.9", BumpKind::Prerelease), "1.2.4-rc.10");
+        assert_eq!(bump("1.2.4-beta", BumpKind::Prerelease), "1.2.4-beta.1");
+    }
+
+    #[test]
+    fn drops_build_metadata() {
+        assert_eq!(bump("1.2.3+build.5", BumpKind::Patch), "1.2.4");
+    }
+}
@@ -1,40 +1,166 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result, anyhow, bail};
 use include_dir::{Dir, DirEntry, include_dir};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::git::clone_repo;
+use crate::build_info;
+use crate::cancel::{self, CancelToken, Cancelled};
+use crate::checksum::sha256_hex;
+use crate::git::{CloneOptions, Repo, clone_repo};
 use crate::github::GitHub;
+use crate::launcher::{BIN_DIR_NAME, INSTALLED_BINARY_NAME, install_launcher};
 use crate::reporter::DynReporter;
 
 static WORKSPACE_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/workspace");
 
 const MANIFEST_PATH: &str = "repos.toml";
-const BIN_DIR_NAME: &str = ".bin";
-const WRAPPER_NAME: &str = "orchestrator";
-const INSTALLED_BINARY_NAME: &str = ".orchestrator-bin";
+const STATE_FILE_NAME: &str = "workspace-state.json";
+const BACKUP_SUFFIX: &str = ".orig";
+const SSH_CLONE_URL_TEMPLATE: &str = "git@github.com:{owner}/{repo}.git";
+const HTTPS_CLONE_URL_TEMPLATE: &str = "https://github.com/{owner}/{repo}.git";
+const CLONE_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CloneProtocol {
+    Ssh,
+    Https,
+}
+
+impl CloneProtocol {
+    fn url_template(self) -> &'static str {
+        match self {
+            CloneProtocol::Ssh => SSH_CLONE_URL_TEMPLATE,
+            CloneProtocol::Https => HTTPS_CLONE_URL_TEMPLATE,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct WorkspaceUpdateArgs {
     pub workspace_root: Option<PathBuf>,
     pub owner: String,
+    pub force: bool,
+    pub protocol: Option<CloneProtocol>,
+    pub clone_url: Option<String>,
+    pub jobs: usize,
+    pub clone_options: CloneOptions,
+    pub keep_versions: usize,
+    pub cancel: CancelToken,
 }
 
 #[derive(Debug, Deserialize)]
 struct WorkspaceManifest {
     repos: Vec<String>,
+    /// Clone URL template with `{owner}` and `{repo}` placeholders.
+    #[serde(default)]
+    clone_url: Option<String>,
+    #[serde(default)]
+    hooks: ManifestHooks,
+}
+
+/// Shell commands run inside repos after workspace-update touches them.
+///
+/// Global hooks run for every repo, followed by any hooks listed for that repo under
+/// `[hooks.repos.<name>]`.
+#[derive(Debug, Default, Deserialize)]
+struct ManifestHooks {
+    #[serde(default)]
+    post_clone: Vec<String>,
+    #[serde(default)]
+    post_sync: Vec<String>,
+    #[serde(default)]
+    repos: BTreeMap<String, RepoHooks>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RepoHooks {
+    #[serde(default)]
+    post_clone: Vec<String>,
+    #[serde(default)]
+    post_sync: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HookKind {
+    PostClone,
+    PostSync,
+}
+
+impl HookKind {
+    fn label(self) -> &'static str {
+        match self {
+            HookKind::PostClone => "post_clone",
+            HookKind::PostSync => "post_sync",
+        }
+    }
+}
+
+impl ManifestHooks {
+    fn commands_for(&self, kind: HookKind, repo: &str) -> Vec<&str> {
+        let (global, per_repo) = match kind {
+            HookKind::PostClone => (
+                &self.post_clone,
+                self.repos.get(repo).map(|h| &h.post_clone),
+            ),
+            HookKind::PostSync => (&self.post_sync, self.repos.get(repo).map(|h| &h.post_sync)),
+        };
+        global
+            .iter()
+            .chain(per_repo.into_iter().flatten())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Bookkeeping persisted under `.bin/` between workspace-update runs.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkspaceState {
+    /// SHA-256 of each synced workspace file as last written by workspace-update,
+    /// keyed by its path relative to the workspace root.
+    #[serde(default)]
+    files: BTreeMap<String, String>,
+    /// Repos cloned by an earlier run whose `post_clone` hooks have not succeeded yet.
+    #[serde(default)]
+    pending_post_clone: BTreeSet<String>,
+}
+
+/// Repos cloned by one run, and one summary per repo that failed to clone.
+struct CloneOutcome {
+    cloned: Vec<String>,
+    failures: Vec<String>,
+}
+
+struct SyncContext<'a> {
+    workspace_root: &'a Path,
+    source_root: &'a Path,
+    force: bool,
+    state: &'a mut WorkspaceState,
+    reporter: &'a DynReporter,
+    updated: usize,
+    skipped: usize,
 }
 
 pub fn run(args: WorkspaceUpdateArgs, reporter: DynReporter) -> Result<()> {
+    update(args, reporter).map_err(|err| match cancel::cancelled(&err) {
+        Some(cancelled) => anyhow!("{cancelled}; re-run workspace-update to continue"),
+        None => err,
+    })
+}
+
+fn update(args: WorkspaceUpdateArgs, reporter: DynReporter) -> Result<()> {
     reporter.step(
         "Workspace Update".to_string(),
         "Bootstrapping repos, syncing workspace files, and installing the local orchestrator launcher.".to_string(),
     );
 
-    let workspace_root = resolve_workspace_root(args.workspace_root)?;
+    let workspace_root = resolve_workspace_root(args.workspace_root.clone())?;
     fs::create_dir_all(&workspace_root).with_context(|| {
         format!(
             "failed to create workspace root at {}",
@@ -44,29 +170,303 @@ pub fn run(args: WorkspaceUpdateArgs, reporter: DynReporter) -> Result<()> {
     reporter.update(format!("workspace_root={}", workspace_root.display()));
 
     let manifest = load_manifest()?;
-    let github = GitHub::new(args.owner.clone(), crate::github::github_token())?;
-
-    let cloned = clone_missing_repos(&workspace_root, &args.owner, &manifest, &github, &reporter)?;
-    let synced = sync_workspace_files(&workspace_root, &reporter)?;
-    let launcher_updated = install_launcher(&workspace_root, &reporter)?;
+    let github = GitHub::new(args.owner.clone(), crate::github::github_token())?
+        .with_cancel(args.cancel.clone());
+
+    let clone_url_template =
+        resolve_clone_url_template(args.clone_url.as_deref(), args.protocol, &manifest)?;
+
+    let clones = clone_missing_repos(
+        &workspace_root,
+        &args,
+        &clone_url_template,
+        &manifest,
+        &github,
+        &reporter,
+    )?;
+    // Recorded before anything else can fail, so a later run still runs their hooks.
+    mark_pending_post_clone(&workspace_root, &manifest, &clones.cloned)?;
+    if args.cancel.is_cancelled() {
+        return Err(Cancelled {
+            stage: "cloning".to_string(),
+        }
+        .into());
+    }
 
-    reporter.ok(format!(
-        "workspace ready (cloned={}, synced_files={}, launcher_updated={})",
-        cloned, synced, launcher_updated
+    let (synced, skipped) = sync_workspace_files(&workspace_root, args.force, &reporter)?;
+    args.cancel.check("installing launcher")?;
+    let launcher_updated = install_launcher(&workspace_root, args.keep_versions, &reporter)?;
+    args.cancel.check("running hooks")?;
+
+    let mut hook_failures = run_pending_post_clone(&workspace_root, &manifest, &reporter)?;
+    hook_failures.extend(run_hooks(
+        &workspace_root,
+        &manifest,
+        HookKind::PostSync,
+        &manifest.repos,
+        &reporter,
     ));
+
+    let mut problems = Vec::new();
+    if !clones.failures.is_empty() {
+        problems.push(format!(
+            "failed to clone {} repo(s):\n{}",
+            clones.failures.len(),
+            clones.failures.join("\n\n")
+        ));
+    }
+    if !hook_failures.is_empty() {
+        problems.push(format!(
+            "workspace hooks failed for {} repo(s):\n{}",
+            hook_failures.len(),
+            hook_failures.join("\n")
+        ));
+    }
+    if !problems.is_empty() {
+        bail!("{}", problems.join("\n\n"));
+    }
+
+    let mut summary = format!(
+        "workspace ready (cloned={}, synced_files={}, skipped_files={}, launcher_updated={})",
+        clones.cloned.len(),
+        synced,
+        skipped,
+        launcher_updated
+    );
+    if skipped > 0 {
+        summary.push_str(&format!(
+            "\nwarning: {skipped} locally modified workspace file(s) were not updated; re-run with --force to overwrite them"
+        ));
+    }
+    if let Some(warning) = check_binary_freshness(&workspace_root, &args.owner) {
+        summary.push_str(&format!("\nwarning: {warning}"));
+    }
+
+    reporter.ok(summary);
+    Ok(())
+}
+
+/// Warns when the running binary was built from a commit older than the
+/// `orchestrator/` checkout, i.e. the launcher we just installed is stale.
+fn check_binary_freshness(workspace_root: &Path, owner: &str) -> Option<String> {
+    let checkout = workspace_root.join("orchestrator");
+    if build_info::GIT_SHA == "unknown" || !checkout.join(".git").exists() {
+        return None;
+    }
+
+    let repo = Repo::new(owner, "orchestrator", &checkout);
+    let head = repo.head_commit().ok()?;
+    if head.starts_with(build_info::GIT_SHA) {
+        return None;
+    }
+
+    match repo.is_ancestor(build_info::GIT_SHA, "HEAD") {
+        Ok(true) => Some(format!(
+            "running orchestrator {} is older than the orchestrator/ checkout ({}); run `self-update` to rebuild the launcher",
+            build_info::GIT_SHA,
+            &head[..head.len().min(12)]
+        )),
+        _ => None,
+    }
+}
+
+/// Runs `kind` hooks in each of `repos`, returning one failure summary per failed repo.
+///
+/// A failing command stops the remaining hooks for that repo but not for other repos.
+fn run_hooks(
+    workspace_root: &Path,
+    manifest: &WorkspaceManifest,
+    kind: HookKind,
+    repos: &[String],
+    reporter: &DynReporter,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+
+    for repo in repos {
+        let commands = manifest.hooks.commands_for(kind, repo);
+        if commands.is_empty() {
+            continue;
+        }
+
+        let repo_dir = workspace_root.join(repo);
+        if !repo_dir.is_dir() {
+            continue;
+        }
+
+        reporter.step(
+            format!("Hooks [{}]", repo),
+            format!("running {} {} hook(s)", commands.len(), kind.label()),
+        );
+        for command in commands {
+            reporter.update(format!("[{repo}] $ {command}"));
+            if let Err(err) = run_hook_command(workspace_root, &repo_dir, repo, command, reporter) {
+                reporter.error(format!("[{repo}] {} hook failed: {err:#}", kind.label()));
+                failures.push(format!("[{repo}] {}: {err:#}", kind.label()));
+                break;
+            }
+        }
+    }
+
+    failures
+}
+
+/// Adds `cloned` to the repos awaiting `post_clone` hooks, dropping any that left the
+/// manifest.
+fn mark_pending_post_clone(
+    workspace_root: &Path,
+    manifest: &WorkspaceManifest,
+    cloned: &[String],
+) -> Result<()> {
+    let mut state = load_state(workspace_root)?;
+    if cloned.is_empty() && state.pending_post_clone.is_empty() {
+        return Ok(());
+    }
+    state.pending_post_clone.extend(cloned.iter().cloned());
+    state
+        .pending_post_clone
+        .retain(|repo| manifest.repos.contains(repo));
+    save_state(workspace_root, &state)
+}
+
+/// Runs `post_clone` hooks for every pending repo; repos whose hooks fail stay pending
+/// for the next run.
+fn run_pending_post_clone(
+    workspace_root: &Path,
+    manifest: &WorkspaceManifest,
+    reporter: &DynReporter,
+) -> Result<Vec<String>> {
+    let mut state = load_state(workspace_root)?;
+    if state.pending_post_clone.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut failures = Vec::new();
+    let mut still_pending = BTreeSet::new();
+    for repo in &state.pending_post_clone {
+        let repo_failures = run_hooks(
+            workspace_root,
+            manifest,
+            HookKind::PostClone,
+            std::slice::from_ref(repo),
+            reporter,
+        );
+        if !repo_failures.is_empty() {
+            still_pending.insert(repo.clone());
+        }
+        failures.extend(repo_failures);
+    }
+
+    state.pending_post_clone = still_pending;
+    save_state(workspace_root, &state)?;
+    Ok(failures)
+}
+
+fn run_hook_command(
+    workspace_root: &Path,
+    repo_dir: &Path,
+    repo: &str,
+    command: &str,
+    reporter: &DynReporter,
+) -> Result<()> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(repo_dir)
+        .env("ORCHESTRATOR_WORKSPACE_ROOT", workspace_root)
+        .env("ORCHESTRATOR_REPO", repo)
+        .output()
+        .with_context(|| format!("failed to run `{command}` in {}", repo_dir.display()))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for line in stdout.lines().chain(stderr.lines()) {
+        if !line.trim().is_empty() {
+            reporter.update(format!("[{repo}] {line}"));
+        }
+    }
+
+    if !output.status.success() {
+        bail!("`{command}` exited with {}", output.status);
+    }
     Ok(())
 }
 
+fn state_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(BIN_DIR_NAME).join(STATE_FILE_NAME)
+}
+
+fn load_state(workspace_root: &Path) -> Result<WorkspaceState> {
+    let path = state_path(workspace_root);
+    match fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text)
+            .with_context(|| format!("failed to parse {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(WorkspaceState::default()),
+        Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+    }
+}
+
+fn save_state(workspace_root: &Path, state: &WorkspaceState) -> Result<()> {
+    let path = state_path(workspace_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let text =
+        serde_json::to_string_pretty(state).context("failed to serialize workspace state")?;
+    fs::write(&path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
 fn load_manifest() -> Result<WorkspaceManifest> {
     let manifest_file = WORKSPACE_DIR
         .get_file(MANIFEST_PATH)
         .ok_or_else(|| anyhow!("embedded workspace manifest {} is missing", MANIFEST_PATH))?;
     let manifest_text = std::str::from_utf8(manifest_file.contents())
         .context("workspace manifest is not valid UTF-8")?;
-    toml::from_str(manifest_text).context("failed to parse embedded workspace manifest")
+    let manifest: WorkspaceManifest =
+        toml::from_str(manifest_text).context("failed to parse embedded workspace manifest")?;
+
+    let unknown: Vec<&str> = manifest
+        .hooks
+        .repos
+        .keys()
+        .filter(|name| !manifest.repos.contains(name))
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        bail!(
+            "workspace manifest has [hooks.repos.*] for repos it doesn't list: {}",
+            unknown.join(", ")
+        );
+    }
+    Ok(manifest)
+}
+
+fn resolve_clone_url_template(
+    explicit: Option<&str>,
+    protocol: Option<CloneProtocol>,
+    manifest: &WorkspaceManifest,
+) -> Result<String> {
+    let template = explicit
+        .or_else(|| protocol.map(CloneProtocol::url_template))
+        .or(manifest.clone_url.as_deref())
+        .unwrap_or(SSH_CLONE_URL_TEMPLATE)
+        .to_string();
+
+    if !template.contains("{repo}") {
+        bail!("clone URL template '{template}' must contain a {{repo}} placeholder");
+    }
+    Ok(template)
 }
 
-fn resolve_workspace_root(explicit: Option<PathBuf>) -> Result<PathBuf> {
+fn render_clone_url(template: &str, owner: &str, repo: &str) -> String {
+    template.replace("{owner}", owner).replace("{repo}", repo)
+}
+
+fn is_github_url(url: &str) -> bool {
+    url.to_lowercase().contains("github.com")
+}
+
+pub fn resolve_workspace_root(explicit: Option<PathBuf>) -> Result<PathBuf> {
     if let Some(path) = explicit {
         return absolutize(path);
     }
@@ -136,12 +536,14 @@ fn looks_like_installed_bin_dir(dir: &Path) -> bool {
 
 fn clone_missing_repos(
     workspace_root: &Path,
-    owner: &str,
+    args: &WorkspaceUpdateArgs,
+    clone_url_template: &str,
     manifest: &WorkspaceManifest,
     github: &GitHub,
     reporter: &DynReporter,
-) -> Result<usize> {
-    let mut cloned = 0usize;
+) -> Result<CloneOutcome> {
+    let owner = args.owner.as_str();
+    let mut pending: Vec<(String, String)> = Vec::new();
 
     for repo in &manifest.repos {
         let repo_dir = workspace_root.join(repo);
@@ -156,50 +558,153 @@ fn clone_missing_repos(
             continue;
         }
 
-        reporter.update(format!("validating GitHub repo {}/{}", owner, repo));
-        let _ = github
-            .get_default_branch(repo)
-            .with_context(|| format!("failed to validate GitHub repo {owner}/{repo}"))?;
+        let clone_url = render_clone_url(clone_url_template, owner, repo);
+
+        // Mirrors and other non-GitHub remotes can't be validated through the API.
+        if is_github_url(&clone_url) {
+            reporter.update(format!("validating GitHub repo {}/{}", owner, repo));
+            let _ = github
+                .get_default_branch(repo)
+                .with_context(|| format!("failed to validate GitHub repo {owner}/{repo}"))?;
+        }
+
+        pending.push((repo.clone(), clone_url));
+    }
 
-        let clone_url = format!("git@github.com:{owner}/{repo}.git");
-        reporter.update(format!("cloning {} into {}", clone_url, repo_dir.display()));
-        clone_repo(workspace_root, &clone_url, repo)?;
-        cloned += 1;
+    if pending.is_empty() {
+        return Ok(CloneOutcome {
+            cloned: Vec::new(),
+            failures: Vec::new(),
+        });
     }
 
-    Ok(cloned)
+    let jobs = args.jobs.clamp(1, pending.len());
+    reporter.update(format!(
+        "cloning {} repo(s) with up to {} parallel job(s)",
+        pending.len(),
+        jobs
+    ));
+    for (repo, _) in &pending {
+        reporter.progress(repo.clone(), "queued".to_string());
+    }
+
+    let queue = Mutex::new(pending.iter());
+    let cloned: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    let failures: Mutex<Vec<(String, anyhow::Error)>> = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                // Clones already running finish; cancellation only stops new ones starting.
+                while !args.cancel.is_cancelled() {
+                    let next = queue.lock().expect("clone queue poisoned").next();
+                    let Some((repo, clone_url)) = next else {
+                        break;
+                    };
+
+                    reporter.progress(repo.clone(), format!("cloning {clone_url}"));
+                    let mut last_report = Instant::now();
+                    let result = clone_repo(
+                        workspace_root,
+                        clone_url,
+                        repo,
+                        &args.clone_options,
+                        |line| {
+                            if last_report.elapsed() >= CLONE_PROGRESS_INTERVAL
+                                || line.ends_with("done.")
+                            {
+                                reporter.progress(repo.clone(), line.to_string());
+                                last_report = Instant::now();
+                            }
+                        },
+                    );
+
+                    match result {
+                        Ok(()) => {
+                            reporter.progress(repo.clone(), "cloned".to_string());
+                            cloned
+                                .lock()
+                                .expect("cloned repos poisoned")
+                                .push(repo.clone());
+                        }
+                        Err(err) => {
+                            reporter.progress(repo.clone(), "FAILED".to_string());
+                            failures
+                                .lock()
+                                .expect("clone failures poisoned")
+                                .push((repo.clone(), err));
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let failures = failures.into_inner().expect("clone failures poisoned");
+    let cloned = cloned.into_inner().expect("cloned repos poisoned");
+    Ok(CloneOutcome {
+        // Manifest order, so hooks run in the same order as on a sequential clone.
+        cloned: pending
+            .into_iter()
+            .map(|(repo, _)| repo)
+            .filter(|repo| cloned.contains(repo))
+            .collect(),
+        failures: failures
+            .iter()
+            .map(|(repo, err)| format!("[{repo}] {err:#}"))
+            .collect(),
+    })
 }
 
-fn sync_workspace_files(workspace_root: &Path, reporter: &DynReporter) -> Result<usize> {
-    let mut updated = 0usize;
+fn sync_workspace_files(
+    workspace_root: &Path,
+    force: bool,
+    reporter: &DynReporter,
+) -> Result<(usize, usize)> {
     let source_root = workspace_root.join("orchestrator").join("workspace");
-    sync_embedded_dir(&WORKSPACE_DIR, workspace_root, &source_root, &mut updated)?;
+    let mut state = load_state(workspace_root)?;
+
+    let mut ctx = SyncContext {
+        workspace_root,
+        source_root: &source_root,
+        force,
+        state: &mut state,
+        reporter,
+        updated: 0,
+        skipped: 0,
+    };
+    sync_embedded_dir(&WORKSPACE_DIR, &mut ctx)?;
+    let (updated, skipped) = (ctx.updated, ctx.skipped);
+
+    save_state(workspace_root, &state)?;
 
     if updated == 0 {
         reporter.update("workspace files already current".to_string());
     } else {
         reporter.update(format!("updated {} workspace file(s)", updated));
     }
+    if skipped > 0 {
+        reporter.error(format!(
+            "skipped {} locally modified workspace file(s); re-run with --force to overwrite them (keeping a {} backup)",
+            skipped, BACKUP_SUFFIX
+        ));
+    }
 
-    Ok(updated)
+    Ok((updated, skipped))
 }
 
-fn sync_embedded_dir(
-    dir: &Dir<'_>,
-    workspace_root: &Path,
-    source_root: &Path,
-    updated: &mut usize,
-) -> Result<()> {
+fn sync_embedded_dir(dir: &Dir<'_>, ctx: &mut SyncContext<'_>) -> Result<()> {
     for entry in dir.entries() {
         match entry {
-            DirEntry::Dir(child) => sync_embedded_dir(child, workspace_root, source_root, updated)?,
+            DirEntry::Dir(child) => sync_embedded_dir(child, ctx)?,
             DirEntry::File(file) => {
                 if file.path() == Path::new(MANIFEST_PATH) {
                     continue;
                 }
 
-                let dest = workspace_root.join(file.path());
-                let source = source_root.join(file.path());
+                let key = file.path().to_string_lossy().replace('\\', "/");
+                let dest = ctx.workspace_root.join(file.path());
+                let source = ctx.source_root.join(file.path());
                 if let Some(parent) = dest.parent() {
                     fs::create_dir_all(parent).with_context(|| {
                         format!("failed to create directory {}", parent.display())
@@ -207,28 +712,72 @@ fn sync_embedded_dir(
                 }
 
                 let contents = file.contents();
+                let embedded_hash = sha256_hex(contents);
                 let mut changed = false;
-                let needs_write = match fs::read(&dest) {
-                    Ok(existing) => existing != contents,
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => true,
+                let existing = match fs::read(&dest) {
+                    Ok(existing) => Some(existing),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
                     Err(err) => {
                         return Err(err)
                             .with_context(|| format!("failed to read {}", dest.display()));
                     }
                 };
 
+                let needs_write = match &existing {
+                    None => true,
+                    Some(existing) if existing == contents => false,
+                    Some(existing) => {
+                        // Only overwrite files whose contents still match what we last
+                        // wrote; anything else was edited locally since.
+                        let existing_hash = sha256_hex(existing);
+                        let recorded = ctx.state.files.get(&key);
+                        let pristine = recorded == Some(&existing_hash);
+
+                        // With no hash on record (e.g. the first run after upgrading)
+                        // there's no proof the file is ours, so a differing file counts
+                        // as modified too.
+                        if !pristine {
+                            if !ctx.force {
+                                ctx.reporter.update(match recorded {
+                                    Some(_) => format!(
+                                        "skipping {}: modified locally since the last workspace-update",
+                                        dest.display()
+                                    ),
+                                    None => format!(
+                                        "skipping {}: differs from the embedded copy and has no recorded hash",
+                                        dest.display()
+                                    ),
+                                });
+                                ctx.skipped += 1;
+                                continue;
+                            }
+
+                            let backup = backup_path(&dest);
+                            fs::write(&backup, existing)
+                                .with_context(|| format!("failed to write {}", backup.display()))?;
+                            ctx.reporter.update(format!(
+                                "overwriting locally modified {} (backup at {})",
+                                dest.display(),
+                                backup.display()
+                            ));
+                        }
+                        true
+                    }
+                };
+
                 if needs_write {
                     fs::write(&dest, contents)
                         .with_context(|| format!("failed to write {}", dest.display()))?;
                     changed = true;
                 }
+                ctx.state.files.insert(key, embedded_hash);
 
                 if sync_file_permissions(&source, &dest)? {
                     changed = true;
                 }
 
                 if changed {
-                    *updated += 1;
+                    ctx.updated += 1;
                 }
             }
         }
@@ -237,81 +786,10 @@ fn sync_embedded_dir(
     Ok(())
 }
 
-fn install_launcher(workspace_root: &Path, reporter: &DynReporter) -> Result<bool> {
-    let bin_dir = workspace_root.join(BIN_DIR_NAME);
-    fs::create_dir_all(&bin_dir)
-        .with_context(|| format!("failed to create {}", bin_dir.display()))?;
-
-    let current_exe = env::current_exe().context("failed to resolve current executable")?;
-    let installed_binary = bin_dir.join(INSTALLED_BINARY_NAME);
-    let wrapper = bin_dir.join(WRAPPER_NAME);
-
-    let mut updated = false;
-
-    if !same_file_path(&current_exe, &installed_binary) {
-        let source = fs::read(&current_exe)
-            .with_context(|| format!("failed to read {}", current_exe.display()))?;
-        let existing = fs::read(&installed_binary).ok();
-        if existing.as_deref() != Some(source.as_slice()) {
-            fs::write(&installed_binary, &source)
-                .with_context(|| format!("failed to write {}", installed_binary.display()))?;
-            set_executable(&installed_binary)?;
-            updated = true;
-        }
-    }
-
-    let wrapper_contents = render_wrapper_script();
-    let existing_wrapper = fs::read_to_string(&wrapper).ok();
-    if existing_wrapper.as_deref() != Some(wrapper_contents.as_str()) {
-        fs::write(&wrapper, wrapper_contents)
-            .with_context(|| format!("failed to write {}", wrapper.display()))?;
-        set_executable(&wrapper)?;
-        updated = true;
-    }
-
-    if updated {
-        reporter.update(format!(
-            "installed workspace launcher at {}",
-            wrapper.display()
-        ));
-    } else {
-        reporter.update(format!(
-            "workspace launcher already current at {}",
-            wrapper.display()
-        ));
-    }
-
-    Ok(updated)
-}
-
-fn render_wrapper_script() -> String {
-    format!(
-        "#!/usr/bin/env sh\nset -e\nSCRIPT_DIR=\"$(CDPATH= cd -- \"$(dirname \"$0\")\" && pwd)\"\nexec \"$SCRIPT_DIR/{}\" \"$@\"\n",
-        INSTALLED_BINARY_NAME
-    )
-}
-
-fn same_file_path(left: &Path, right: &Path) -> bool {
-    match (fs::canonicalize(left), fs::canonicalize(right)) {
-        (Ok(a), Ok(b)) => a == b,
-        _ => left == right,
-    }
-}
-
-fn set_executable(path: &Path) -> Result<()> {
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-
-        let mut perms = fs::metadata(path)
-            .with_context(|| format!("failed to read metadata for {}", path.display()))?
-            .permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(path, perms)
-            .with_context(|| format!("failed to set permissions on {}", path.display()))?;
-    }
-
-    Ok(())
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    PathBuf::from(name)
 }
 
 fn sync_file_permissions(source: &Path, dest: &Path) -> Result<bool> {